@@ -0,0 +1,30 @@
+use schemars::JsonSchema;
+use type_gen::Generator;
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct User {
+    name: String,
+    address: Address,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Address {
+    street: String,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Order {
+    user: User,
+    delivery: Address,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    //in a build.rs this would be `.write_to("path/to/Types.fs")` instead
+    for file in Generator::new().add::<User>().add::<Order>().files()? {
+        println!("// {}\n{}", file.name, file.contents);
+    }
+    Ok(())
+}
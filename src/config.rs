@@ -0,0 +1,279 @@
+use std::{collections::BTreeMap, fmt, fs, io, path::Path};
+
+use serde::Deserialize;
+
+use crate::{FileHeader, Grouping, ANY_JSON_VALUE};
+
+///The settings for generating types, as read from a `type_gen.toml` or `type_gen.json` file.
+///
+///```toml
+///[output]
+///namespace = "Api"
+///opens = ["System"]
+///split = true
+///serializer = "system-text-json"
+///optional_fields = "voption"
+///
+///[naming]
+///convention = "underscore"
+///hints = { "User.address" = "Location" }
+///
+///[type_overrides]
+///DateTime = "System.DateTime"
+///
+///[formats]
+///uuid = "System.Guid"
+///```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub output: OutputConfig,
+    pub naming: NamingConfig,
+    ///Types that don't get generated, but get replaced by the given F# type.
+    ///The keys are the names schemars gives to the types.
+    pub type_overrides: BTreeMap<String, String>,
+    ///The F# type to use for values with the given `format`, like `date-time` or `uuid`.
+    pub formats: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    ///Start every file with `namespace X`. This is the default, using `Types`.
+    pub namespace: Option<String>,
+    ///Start every file with `module X` instead. Can't be used together with `namespace`.
+    pub module: Option<String>,
+    ///Extra namespaces to open in every file.
+    pub opens: Vec<String>,
+    ///Write a file for every Rust module, or for every schema file when using the binary,
+    ///instead of a single file.
+    pub split: bool,
+    ///The name of the file when everything ends up in a single one, without `.fs`.
+    pub file_name: String,
+    ///The library that is used to read and write the json.
+    pub serializer: Serializer,
+    ///What fields that can be missing from the json become.
+    pub optional_fields: OptionalFields,
+    ///Give every record a `validate` function, which checks the constraints of the schema.
+    pub validate: bool,
+    ///Give strings and numbers with constraints a type of their own, which can only be made
+    ///through a `create` function that checks the constraints.
+    pub wrap_constrained: bool,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            namespace: None,
+            module: None,
+            opens: Vec::new(),
+            split: false,
+            file_name: "Types".to_owned(),
+            serializer: Serializer::default(),
+            optional_fields: OptionalFields::default(),
+            validate: false,
+            wrap_constrained: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NamingConfig {
+    pub convention: NamingConvention,
+    ///Names for types without a name of their own, like `"User.address" = "Location"` for the
+    ///type of the field `address` in `User`.
+    pub hints: BTreeMap<String, String>,
+}
+
+///The built in naming strategies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NamingConvention {
+    ///See [crate::PascalCaseNaming]
+    #[default]
+    PascalCase,
+    ///See [crate::UnderscoreNaming]
+    Underscore,
+}
+
+///The json libraries the generated code can be made for. They decide which attributes, opens and
+///converters get generated, so the json matches what serde reads and writes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum Serializer {
+    ///FSharp.Json. Types it can't handle on its own get `deserialize` and `serialize` functions,
+    ///and record fields holding them use the `Transform` in their module. Using these types
+    ///anywhere else inside another type, like in an array, is an error. Record fields holding an
+    ///integer enum are written as the number. Enums anywhere else need
+    ///`JsonConfig.create(enumValue = EnumMode.Value)` for that. FSharp.Json writes `None` as
+    ///`null`, which serde doesn't accept for fields that can be missing, so these need
+    ///`JsonConfig.create(serializeNone = SerializeNone.Omit)` as well.
+    #[default]
+    #[serde(rename = "fsharp-json")]
+    FSharpJson,
+    ///Newtonsoft.Json. Unions get a `Converter` in their module, which needs to be added to the
+    ///`JsonSerializerSettings`. `option` needs a converter as well, like the one of FSharpLu.Json.
+    #[serde(rename = "newtonsoft")]
+    Newtonsoft,
+    ///System.Text.Json together with FSharp.SystemTextJson. Most types get a
+    ///`[<JsonFSharpConverter>]` attribute, untagged unions, unions of literal values and wrapper
+    ///types get a `Converter` in their module instead, which needs to be added to the
+    ///`JsonSerializerOptions`.
+    #[serde(rename = "system-text-json")]
+    SystemTextJson,
+    ///Thoth.Json. Every type gets a `decoder` and an `encode` function in its module.
+    #[serde(rename = "thoth")]
+    Thoth,
+}
+
+impl Serializer {
+    ///Gets the serializer by the name used in the config, like `system-text-json`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_owned())).ok()
+    }
+    ///The namespaces every file needs to open.
+    pub fn opens(self) -> &'static [&'static str] {
+        match self {
+            Serializer::FSharpJson => &["FSharp.Json"],
+            Serializer::Newtonsoft => &["Newtonsoft.Json", "Newtonsoft.Json.Linq"],
+            Serializer::SystemTextJson => &["System.Text.Json", "System.Text.Json.Serialization"],
+            Serializer::Thoth => &["Thoth.Json"],
+        }
+    }
+    ///Whether the generated code can use `value` for a field of the type `type_name` that is
+    ///missing from the json.
+    pub(crate) fn fills_default(self, value: &serde_json::Value, type_name: &str) -> bool {
+        match self {
+            Serializer::Thoth => true,
+            Serializer::Newtonsoft => {
+                matches!(type_name, "string" | "int" | "float" | "bool")
+                    && (value.is_string() || value.is_number() || value.is_boolean())
+            }
+            Serializer::FSharpJson | Serializer::SystemTextJson => false,
+        }
+    }
+    ///Whether the serializer can read and write `voption`.
+    pub(crate) fn supports_value_options(self) -> bool {
+        matches!(self, Serializer::SystemTextJson | Serializer::Thoth)
+    }
+    ///The type used for values that can hold any json.
+    pub fn any_json_type(self) -> &'static str {
+        match self {
+            Serializer::FSharpJson | Serializer::Thoth => ANY_JSON_VALUE,
+            Serializer::Newtonsoft => "JToken",
+            Serializer::SystemTextJson => "JsonElement",
+        }
+    }
+}
+
+///What a field becomes when it is not `required`, which is how schemars describes fields with
+///`#[serde(default)]` or `#[serde(skip_serializing_if = "...")]`.
+///Fields that are an `option` already stay as they are.
+///
+///serde doesn't accept `null` for the fields that become optional, so `None` has to be left out
+///of the json. Thoth and Newtonsoft.Json leave it out on their own, FSharp.Json needs
+///`JsonConfig.create(serializeNone = SerializeNone.Omit)`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OptionalFields {
+    ///`option<T>`.
+    #[default]
+    Option,
+    ///`voption<T>`, which only System.Text.Json and Thoth know how to read and write. With the
+    ///other serializers, fields become `option<T>` instead.
+    #[serde(rename = "voption")]
+    ValueOption,
+    ///`T`, using the default of the schema when the field is missing. This needs a serializer
+    ///that can fill in the default, which are Thoth, and Newtonsoft.Json for defaults that are a
+    ///string, number or bool. Other fields become `option<T>`.
+    Default,
+}
+
+impl OptionalFields {
+    ///Gets the setting by the name used in the config, like `voption`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_owned())).ok()
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    ///The file could be read, but the settings don't make sense together.
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(x) => write!(f, "Could not read the config: {}", x),
+            ConfigError::Toml(x) => write!(f, "The config is not valid: {}", x),
+            ConfigError::Json(x) => write!(f, "The config is not valid: {}", x),
+            ConfigError::Invalid(x) => write!(f, "The config is not valid: {}", x),
+        }
+    }
+}
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    pub fn from_toml(config: &str) -> Result<Self, ConfigError> {
+        toml::from_str::<Self>(config)
+            .map_err(ConfigError::Toml)
+            .and_then(Self::validated)
+    }
+    pub fn from_json(config: &str) -> Result<Self, ConfigError> {
+        serde_json::from_str::<Self>(config)
+            .map_err(ConfigError::Json)
+            .and_then(Self::validated)
+    }
+    ///Reads the config at `path`. Files ending with `.json` are read as json, everything else
+    ///as toml.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(ConfigError::Io)?;
+        match path.extension().and_then(|v| v.to_str()) {
+            Some("json") => Self::from_json(&contents),
+            _ => Self::from_toml(&contents),
+        }
+    }
+    fn validated(self) -> Result<Self, ConfigError> {
+        if self.output.namespace.is_some() && self.output.module.is_some() {
+            return Err(ConfigError::Invalid(
+                "output.namespace and output.module can't both be set".to_owned(),
+            ));
+        }
+        if let Some(hint) = self.naming.hints.keys().find(|v| !v.contains('.')) {
+            return Err(ConfigError::Invalid(format!(
+                "the naming hint `{}` should look like `Parent.part`",
+                hint
+            )));
+        }
+        Ok(self)
+    }
+    ///The parent and part of every naming hint.
+    pub(crate) fn name_hints(&self) -> impl Iterator<Item = (&str, &str, &str)> {
+        self.naming.hints.iter().filter_map(|(key, name)| {
+            key.rsplit_once('.')
+                .map(|(parent, part)| (parent, part, name.as_str()))
+        })
+    }
+}
+
+impl OutputConfig {
+    pub fn header(&self) -> FileHeader {
+        match (&self.namespace, &self.module) {
+            (_, Some(module)) => FileHeader::Module(module.to_owned()),
+            (Some(namespace), None) => FileHeader::Namespace(namespace.to_owned()),
+            (None, None) => FileHeader::Namespace("Types".to_owned()),
+        }
+    }
+    pub fn grouping(&self) -> Grouping {
+        if self.split {
+            Grouping::ByModulePath
+        } else {
+            Grouping::SingleFile(self.file_name.to_owned())
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use std::{fmt, io};
+
+use serde_json::{Number, Value};
+
+///A generated type, as it will be written out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeDefinition {
+    pub name: String,
+    ///The documentation written above the type, taken from the schema.
+    pub docs: Option<String>,
+    ///Deprecated types are marked `[<System.Obsolete>]`.
+    pub deprecated: bool,
+    ///The type parameters of a generic type, like `'T`.
+    pub type_params: Vec<String>,
+    ///The attributes placed above the type.
+    pub attributes: Vec<String>,
+    pub kind: TypeKind,
+    ///How serde writes the type as json.
+    pub representation: Representation,
+    ///Functions that get placed in a module with the same name as the type.
+    ///They are written without indentation, the renderer takes care of that.
+    pub functions: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeKind {
+    Record(Vec<Field>),
+    Union(Vec<UnionCase>),
+    ///An F# enum, every case has the value it represents.
+    Enum(Vec<(String, String)>),
+    ///A union with a single private case named like the type, holding a value of the type `of`.
+    ///Values can only be made by the `create` function in its module, which checks the
+    ///constraints.
+    Wrapper {
+        of: String,
+        constraints: Vec<Constraint>,
+    },
+}
+
+///How serde writes a type as json, which is what any generated serialization code has to match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Representation {
+    ///An object with a property for every field. With `extra_fields` the last field is a map
+    ///holding every property that is not described by the schema.
+    Object { extra_fields: bool },
+    ///`"Case"` for cases without a value and `{ "Case": value }` for the others.
+    ExternallyTagged,
+    ///An object with the name of the case stored in the property `tag`, next to the fields of the
+    ///value.
+    InternallyTagged { tag: String },
+    ///Just the value, without anything saying which case it is.
+    Untagged,
+    ///Every case stands for a single json value, in the same order as the cases.
+    Literals(Vec<Value>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub type_name: String,
+    ///The value serde uses when the field is missing from the json, which the serializer has to
+    ///fill in as the field is not an `option`.
+    pub default: Option<Value>,
+    ///Whether the field is only an `option` because it can be missing from the json. serde
+    ///doesn't accept `null` for these fields, so `None` has to be left out of the json.
+    pub skip_if_none: bool,
+    ///What the schema requires of the value, beyond its type.
+    pub constraints: Vec<Constraint>,
+    pub docs: Option<String>,
+    pub deprecated: bool,
+    pub attributes: Vec<String>,
+}
+
+///A requirement the schema puts on a value, like the ones from `#[validate(length(min = 1))]`.
+///Lengths are about strings and the amount of items about arrays.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Constraint {
+    ///The only value allowed, from a `const` or an `enum` with a single value.
+    Const(Value),
+    MinLength(u32),
+    MaxLength(u32),
+    ///A regular expression the string has to match somewhere.
+    Pattern(String),
+    Minimum(Number),
+    Maximum(Number),
+    ExclusiveMinimum(Number),
+    ExclusiveMaximum(Number),
+    MinItems(u32),
+    MaxItems(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnionCase {
+    pub name: String,
+    ///The name serde uses for the case, which only matters for tagged unions.
+    pub wire_name: String,
+    pub docs: Option<String>,
+    pub deprecated: bool,
+    pub attributes: Vec<String>,
+    ///The type of the value stored in this case, if it stores one.
+    pub of: Option<String>,
+}
+
+impl TypeDefinition {
+    pub(crate) fn new(name: &str, kind: TypeKind, representation: Representation) -> Self {
+        Self {
+            name: name.to_owned(),
+            docs: None,
+            deprecated: false,
+            type_params: Vec::new(),
+            attributes: Vec::new(),
+            kind,
+            representation,
+            functions: Vec::new(),
+        }
+    }
+    ///The name used to refer to this type, including its type parameters.
+    pub fn full_name(&self) -> String {
+        if self.type_params.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}<{}>", self.name, self.type_params.join(", "))
+        }
+    }
+    ///The types used by the fields and cases of this type.
+    pub fn used_types(&self) -> Vec<&str> {
+        match &self.kind {
+            TypeKind::Record(fields) => fields.iter().map(|v| v.type_name.as_str()).collect(),
+            TypeKind::Union(cases) => cases.iter().filter_map(|v| v.of.as_deref()).collect(),
+            TypeKind::Enum(_) => Vec::new(),
+            TypeKind::Wrapper { of, .. } => vec![of.as_str()],
+        }
+    }
+    ///The names of the types used by the fields and cases of this type, like `Map` and `User`
+    ///for a field of the type `Map<string,User>`.
+    pub(crate) fn used_names(&self) -> impl Iterator<Item = &str> {
+        self.used_types()
+            .into_iter()
+            .flat_map(|v| v.split(|v: char| !(v.is_alphanumeric() || v == '_')))
+            .filter(|v| !v.is_empty())
+    }
+    ///Whether the type or any of its fields or cases is deprecated.
+    pub fn contains_deprecated(&self) -> bool {
+        self.deprecated
+            || match &self.kind {
+                TypeKind::Record(fields) => fields.iter().any(|v| v.deprecated),
+                TypeKind::Union(cases) => cases.iter().any(|v| v.deprecated),
+                TypeKind::Enum(_) | TypeKind::Wrapper { .. } => false,
+            }
+    }
+    ///Writes the type to `out`, without a newline at the end.
+    pub fn write_to(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        Self::write_group_to(&[self], out)
+    }
+    ///Writes types that use each other to `out` as a single recursive group, the first starting
+    ///with `type` and the others with `and`, followed by their modules. There is no newline at
+    ///the end.
+    ///
+    ///When more than one of the types has a module, the modules go in an opened `module rec`,
+    ///so their functions can use the ones of the modules after them.
+    pub fn write_group_to(
+        definitions: &[&TypeDefinition],
+        out: &mut impl fmt::Write,
+    ) -> fmt::Result {
+        let mut out = Renderer::new(out);
+        for (i, definition) in definitions.iter().enumerate() {
+            definition.write_type(&mut out, i == 0)?;
+        }
+        let modules = definitions
+            .iter()
+            .filter(|v| !v.functions.is_empty())
+            .count();
+        let indentation = if modules > 1 {
+            out.line(0, "[<AutoOpen>]")?;
+            out.line(0, &format!("module rec {}Functions =", definitions[0].name))?;
+            1
+        } else {
+            0
+        };
+        for definition in definitions {
+            definition.write_module(&mut out, indentation)?;
+        }
+        Ok(())
+    }
+    ///writes the type itself, starting with `type` when it is the `first` of its group and with
+    ///`and` otherwise. F# only allows the attributes of the other types after the `and`.
+    fn write_type<W: fmt::Write>(&self, out: &mut Renderer<W>, first: bool) -> fmt::Result {
+        out.docs(0, self.docs.as_deref())?;
+        if first {
+            if self.deprecated {
+                out.line(0, OBSOLETE)?;
+            }
+            for attribute in &self.attributes {
+                out.line(0, attribute)?;
+            }
+            out.line(0, &format!("type {} =", self.full_name()))?;
+        } else {
+            out.line(
+                0,
+                &format!(
+                    "and {}{} =",
+                    render_attributes(self.deprecated, &self.attributes),
+                    self.full_name()
+                ),
+            )?;
+        }
+        match &self.kind {
+            TypeKind::Record(fields) => {
+                out.line(1, "{")?;
+                for field in fields {
+                    out.docs(2, field.docs.as_deref())?;
+                    out.line(
+                        2,
+                        &format!(
+                            "{}{} : {}",
+                            render_attributes(field.deprecated, &field.attributes),
+                            field.name,
+                            field.type_name
+                        ),
+                    )?;
+                }
+                out.line(1, "}")?;
+            }
+            TypeKind::Union(cases) => {
+                for case in cases {
+                    let of = case
+                        .of
+                        .as_ref()
+                        .map(|v| format!(" of {}", v))
+                        .unwrap_or_default();
+                    out.docs(1, case.docs.as_deref())?;
+                    out.line(
+                        1,
+                        &format!(
+                            "| {}{}{}",
+                            render_attributes(case.deprecated, &case.attributes),
+                            case.name,
+                            of
+                        ),
+                    )?;
+                }
+            }
+            TypeKind::Enum(cases) => {
+                for (name, value) in cases {
+                    out.line(1, &format!("| {} = {}", name, value))?;
+                }
+            }
+            TypeKind::Wrapper { of, .. } => {
+                out.line(1, &format!("private {} of {}", self.name, of))?;
+            }
+        }
+        Ok(())
+    }
+    ///writes the module with the functions of the type, if it has any.
+    fn write_module<W: fmt::Write>(
+        &self,
+        out: &mut Renderer<W>,
+        indentation: usize,
+    ) -> fmt::Result {
+        if !self.functions.is_empty() {
+            out.line(indentation, &format!("module {} =", self.name))?;
+            for (i, function) in self.functions.iter().enumerate() {
+                if i != 0 {
+                    out.line(0, "")?;
+                }
+                for line in function.lines() {
+                    out.line(indentation + 1, line)?;
+                }
+            }
+        }
+        Ok(())
+    }
+    ///Writes the type to `out`, without a newline at the end.
+    pub fn write_to_io(&self, out: &mut impl io::Write) -> io::Result<()> {
+        Self::write_group_to_io(&[self], out)
+    }
+    ///Like [TypeDefinition::write_group_to], but for an [io::Write].
+    pub fn write_group_to_io(
+        definitions: &[&TypeDefinition],
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        let mut adapter = IoAdapter { out, error: None };
+        match Self::write_group_to(definitions, &mut adapter) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(adapter
+                .error
+                .unwrap_or_else(|| io::Error::other("formatter error"))),
+        }
+    }
+}
+
+impl fmt::Display for TypeDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_to(f)
+    }
+}
+
+///the attribute that makes the F# compiler warn about using something that is deprecated.
+///schemars doesn't keep the note of `#[deprecated]`, so the message only says where it comes from.
+const OBSOLETE: &str = "[<System.Obsolete(\"Deprecated in the schema\")>]";
+
+fn render_attributes(deprecated: bool, attributes: &[String]) -> String {
+    deprecated
+        .then_some(OBSOLETE)
+        .into_iter()
+        .chain(attributes.iter().map(String::as_str))
+        .map(|v| format!("{} ", v))
+        .collect()
+}
+
+///writes lines with the given indentation, putting the newlines between lines rather than after them
+struct Renderer<'a, W> {
+    out: &'a mut W,
+    first_line: bool,
+}
+
+impl<'a, W: fmt::Write> Renderer<'a, W> {
+    fn new(out: &'a mut W) -> Self {
+        Self {
+            out,
+            first_line: true,
+        }
+    }
+    fn line(&mut self, indentation: usize, text: &str) -> fmt::Result {
+        if !self.first_line {
+            self.out.write_char('\n')?;
+        }
+        self.first_line = false;
+        if !text.is_empty() {
+            for _ in 0..indentation {
+                self.out.write_str("    ")?;
+            }
+            self.out.write_str(text)?;
+        }
+        Ok(())
+    }
+    ///writes the documentation as `///` comments, which F# turns into the summary of what follows.
+    fn docs(&mut self, indentation: usize, docs: Option<&str>) -> fmt::Result {
+        for line in docs.into_iter().flat_map(str::lines) {
+            match line.trim_end() {
+                "" => self.line(indentation, "///")?,
+                line => self.line(indentation, &format!("/// {}", line))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+///lets an [io::Write] be used as a [fmt::Write], keeping the error around so it can be returned
+struct IoAdapter<'a, W> {
+    out: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<'a, W: io::Write> fmt::Write for IoAdapter<'a, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.out.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
@@ -0,0 +1,91 @@
+use std::rc::Rc;
+
+use schemars::{schema::RootSchema, JsonSchema};
+
+use crate::{gen, writer::get_module_path, ExternalTypeCollector, Result, TypeDefinition};
+
+///A type registered with `#[type_gen::export]` or [export_type!](crate::export_type).
+pub struct ExportedType {
+    type_name: fn() -> &'static str,
+    schema: fn() -> RootSchema,
+}
+
+inventory::collect!(ExportedType);
+
+fn schema_of<A: JsonSchema>() -> RootSchema {
+    schemars::schema_for!(A)
+}
+
+impl ExportedType {
+    pub const fn new<A: JsonSchema>() -> Self {
+        Self {
+            type_name: std::any::type_name::<A>,
+            schema: schema_of::<A>,
+        }
+    }
+    ///The full Rust name of the type, like `my_crate::api::User`.
+    pub fn type_name(&self) -> &'static str {
+        (self.type_name)()
+    }
+    ///The module the type is defined in, like `my_crate::api`.
+    pub fn module_path(&self) -> String {
+        get_module_path(self.type_name())
+    }
+    pub fn schema(&self) -> RootSchema {
+        (self.schema)()
+    }
+}
+
+///Registers a type so it gets generated by [gen_exported] and
+///[Generator::add_exported](crate::Generator::add_exported).
+///
+///This is what `#[type_gen::export]` uses, but it also works for types that can't get the
+///attribute, like instances of generic types or types from other crates.
+///
+///```
+///#[derive(schemars::JsonSchema)]
+///struct Page<T> {
+///    items: Vec<T>,
+///}
+///type_gen::export_type!(Page<String>);
+///```
+#[macro_export]
+macro_rules! export_type {
+    ($type:ty) => {
+        $crate::inventory::submit! {
+            $crate::ExportedType::new::<$type>()
+        }
+    };
+}
+
+///Every registered type, ordered by their Rust name so the order doesn't depend on the linker.
+///
+///The registration happens at link time, so only types from crates that end up in the final
+///binary are found. In a `build.rs` that means the crate with the types has to be a
+///build-dependency that is used by the build script.
+pub fn exported_types() -> Vec<&'static ExportedType> {
+    let mut types = inventory::iter::<ExportedType>
+        .into_iter()
+        .collect::<Vec<_>>();
+    types.sort_by_key(|v| v.type_name());
+    types
+}
+
+///Generates every registered type and the types they need, and returns the registered ones.
+///The types they need can be found with [ExternalTypeCollector::get_new_types].
+pub fn gen_exported(x: &mut ExternalTypeCollector) -> Result<Vec<Rc<TypeDefinition>>> {
+    let schemas = exported_types()
+        .into_iter()
+        .map(|exported| exported.schema())
+        .collect::<Vec<_>>();
+    for schema in &schemas {
+        x.reserve_names(schema)?;
+    }
+    schemas
+        .into_iter()
+        .map(|schema| {
+            let key = <&TypeDefinition>::from(gen(schema, x)?).full_name();
+            Ok(Rc::clone(&x.types[&key]))
+        })
+        .collect()
+}
@@ -0,0 +1,296 @@
+use serde_json::Value;
+
+use crate::{
+    to_fsharp_string, Error, Field, Representation, Result, TypeDefinition, TypeKind, UnionCase,
+    ANY_JSON_VALUE,
+};
+
+///Adds what FSharp.Json needs to read and write the type the same way serde does.
+///
+///Renamed cases get a `[<JsonUnionCase>]` attribute and internally tagged unions a `[<JsonUnion>]`
+///one. Types FSharp.Json can't handle on its own get `deserialize` and `serialize` functions,
+///and a `Transform` for using them as field of a record.
+pub(crate) fn add_converters(definition: &mut TypeDefinition) {
+    let type_name = definition.name.clone();
+    if let Representation::InternallyTagged { tag } = &definition.representation {
+        definition.attributes.push(format!(
+            "[<JsonUnion(Mode = UnionMode.CaseKeyDiscriminatorField, CaseKeyField = {})>]",
+            to_fsharp_string(tag)
+        ));
+    }
+    let functions = match (&mut definition.kind, &definition.representation) {
+        (TypeKind::Record(fields), Representation::Object { extra_fields: true }) => {
+            match fields.split_last() {
+                Some((extra, known)) => {
+                    let mut functions = gen_extra_fields_converter(known, extra, &type_name);
+                    functions.push(gen_json_transform(&type_name));
+                    functions
+                }
+                None => Vec::new(),
+            }
+        }
+        (
+            TypeKind::Union(cases),
+            Representation::ExternallyTagged | Representation::InternallyTagged { .. },
+        ) => {
+            for case in cases.iter_mut().filter(|v| v.name != v.wire_name) {
+                case.attributes.push(format!(
+                    "[<JsonUnionCase({})>]",
+                    to_fsharp_string(&case.wire_name)
+                ));
+            }
+            Vec::new()
+        }
+        (TypeKind::Union(cases), Representation::Untagged) => {
+            let mut functions = gen_untagged_union_converter(cases, &type_name);
+            functions.push(gen_json_transform(&type_name));
+            functions
+        }
+        (TypeKind::Union(cases), Representation::Literals(values)) => {
+            let mut functions = gen_literal_converter(cases, values, &type_name);
+            functions.push(gen_json_transform(&type_name));
+            functions
+        }
+        (TypeKind::Wrapper { of, .. }, _) => gen_wrapper_converter(of, &type_name),
+        _ => Vec::new(),
+    };
+    definition.functions.extend(functions);
+}
+
+///Makes the type use the types that FSharp.Json can only handle through their own functions,
+///which `get` looks up by name.
+///
+///Record fields holding such a type, on its own or in an `option`, get a `[<JsonField>]`
+///attribute with the `Transform` of the type. FSharp.Json has no way to use the functions
+///anywhere else, so any other use of these types results in [Error::TypeCantBeNested].
+///Record fields holding an integer enum get told to use the value of the enum, as FSharp.Json
+///writes the name of the case by default.
+pub(crate) fn use_nested_types<'a>(
+    definition: &mut TypeDefinition,
+    get: impl Fn(&str) -> Option<&'a TypeDefinition>,
+) -> Result<()> {
+    let with_functions = |name: &str| get(name).filter(|v| needs_functions(v));
+    match &mut definition.kind {
+        TypeKind::Record(fields) => {
+            for field in fields {
+                let inner = field
+                    .type_name
+                    .strip_prefix("option<")
+                    .and_then(|v| v.strip_suffix('>'))
+                    .unwrap_or(&field.type_name);
+                match get(inner) {
+                    Some(nested) if needs_functions(nested) => {
+                        field.attributes.push(format!(
+                            "[<JsonField(Transform = typeof<{}.Transform>)>]",
+                            nested.name
+                        ));
+                    }
+                    Some(TypeDefinition {
+                        kind: TypeKind::Enum(_),
+                        ..
+                    }) => {
+                        field
+                            .attributes
+                            .push("[<JsonField(EnumValue = EnumMode.Value)>]".to_owned());
+                    }
+                    _ if type_names(&field.type_name).any(|v| with_functions(v).is_some()) => {
+                        return Err(Error::TypeCantBeNested)
+                    }
+                    _ => (),
+                }
+            }
+        }
+        TypeKind::Union(cases) => {
+            let mut used = cases.iter().filter_map(|v| v.of.as_deref());
+            if used.any(|v| type_names(v).any(|v| with_functions(v).is_some())) {
+                return Err(Error::TypeCantBeNested);
+            }
+        }
+        TypeKind::Enum(_) | TypeKind::Wrapper { .. } => (),
+    }
+    Ok(())
+}
+
+///whether FSharp.Json can only read and write the type through the functions in its module
+fn needs_functions(definition: &TypeDefinition) -> bool {
+    matches!(
+        (&definition.kind, &definition.representation),
+        (TypeKind::Wrapper { .. }, _)
+            | (
+                TypeKind::Union(_),
+                Representation::Untagged | Representation::Literals(_)
+            )
+            | (
+                TypeKind::Record(_),
+                Representation::Object { extra_fields: true }
+            )
+    )
+}
+
+///the names of the types that make up `type_name`, like `Map` and `User` for `Map<string,User>`
+fn type_names(type_name: &str) -> impl Iterator<Item = &str> {
+    type_name
+        .split(|v: char| !(v.is_alphanumeric() || v == '_'))
+        .filter(|v| !v.is_empty())
+}
+
+///generates the functions that move the properties that are not part of the record in and out of
+///the extra fields, as FSharp.Json can't do that on its own.
+fn gen_extra_fields_converter(known: &[Field], extra: &Field, type_name: &str) -> Vec<String> {
+    let known_fields = known
+        .iter()
+        .map(|v| to_fsharp_string(&v.name))
+        .collect::<Vec<_>>()
+        .join("; ");
+    let extra_name = &extra.name;
+    let extra_fields_name = to_fsharp_string(extra_name);
+    let extra_fields_type = extra
+        .type_name
+        .strip_prefix("Map<string,")
+        .and_then(|v| v.strip_suffix('>'))
+        .unwrap_or(ANY_JSON_VALUE);
+    let (decode, encode) = if extra_fields_type == ANY_JSON_VALUE {
+        (String::new(), String::new())
+    } else {
+        (
+            format!(
+                " |> Array.map (fun (key, value) -> key, Json.deserialize<{}> (value.ToString()))",
+                extra_fields_type
+            ),
+            " |> Array.map (fun (key, value) -> key, JsonValue.Parse (Json.serialize value))"
+                .to_owned(),
+        )
+    };
+    vec![
+        format!("let private knownFields = set [ {known_fields} ]"),
+        format!(
+            "let deserialize (json : string) : {type_name} =
+    match JsonValue.Parse json with
+    | JsonValue.Record fields ->
+        let known, extra = fields |> Array.partition (fun (key, _) -> knownFields.Contains key)
+        let withoutExtra = JsonValue.Record (Array.append known [| {extra_fields_name}, JsonValue.Record [||] |])
+        let value = Json.deserialize<{type_name}> (withoutExtra.ToString())
+        {{ value with {extra_name} = extra{decode} |> Map.ofArray }}
+    | _ -> failwith \"Expected a json object for {type_name}\""
+        ),
+        format!(
+            "let serialize (value : {type_name}) : string =
+    match JsonValue.Parse (Json.serialize {{ value with {extra_name} = Map.empty }}) with
+    | JsonValue.Record fields ->
+        let known = fields |> Array.filter (fun (key, _) -> key <> {extra_fields_name})
+        let extra = value.{extra_name} |> Map.toArray{encode}
+        (JsonValue.Record (Array.append known extra)).ToString()
+    | _ -> failwith \"Expected {type_name} to serialize to a json object\""
+        ),
+    ]
+}
+
+///generates the functions that read an untagged union by trying every case in order,
+///and write it without saying which case it was.
+fn gen_untagged_union_converter(cases: &[UnionCase], type_name: &str) -> Vec<String> {
+    let attempts = cases
+        .iter()
+        .filter_map(|case| {
+            case.of.as_ref().map(|of| {
+                format!(
+                    "        (fun () -> {} (Json.deserialize<{}> json))",
+                    case.name, of
+                )
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let writers = cases
+        .iter()
+        .map(|case| match case.of {
+            Some(_) => format!("    | {} value -> Json.serialize value", case.name),
+            None => format!("    | {} -> \"null\"", case.name),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    vec![
+        format!(
+            "let deserialize (json : string) : {type_name} =
+    [
+{attempts}
+    ]
+    |> List.tryPick (fun attempt -> try Some (attempt ()) with _ -> None)
+    |> Option.defaultWith (fun () -> failwithf \"Could not read %s as {type_name}\" json)"
+        ),
+        format!(
+            "let serialize (value : {type_name}) : string =
+    match value with
+{writers}"
+        ),
+    ]
+}
+
+///generates a `Transform` that goes through the json value written by `serialize` and read by
+///`deserialize`, for types that FSharp.Json can't read and write on its own.
+fn gen_json_transform(type_name: &str) -> String {
+    format!(
+        "type Transform() =
+    interface ITypeTransform with
+        member _.targetType () = typeof<JsonValue>
+        member _.toTargetType value = box (JsonValue.Parse (serialize (value :?> {type_name})))
+        member _.fromTargetType json = box (deserialize ((json :?> JsonValue).ToString()))"
+    )
+}
+
+///generates the functions that read the wrapped value and only wrap it when `create` accepts it.
+fn gen_wrapper_converter(of: &str, type_name: &str) -> Vec<String> {
+    vec![
+        format!(
+            "let deserialize (json : string) : {type_name} =
+    match create (Json.deserialize<{of}> json) with
+    | Ok value -> value
+    | Error errors -> failwithf \"Could not read %s as {type_name}: %s\" json (String.concat \", \" errors)"
+        ),
+        format!(
+            "let serialize (value : {type_name}) : string =
+    match value with
+    | {type_name} value -> Json.serialize value"
+        ),
+        format!(
+            "type Transform() =
+    interface ITypeTransform with
+        member _.targetType () = typeof<{of}>
+        member _.toTargetType wrapped = box (value (wrapped :?> {type_name}))
+        member _.fromTargetType unwrapped =
+            match create (unwrapped :?> {of}) with
+            | Ok value -> box value
+            | Error errors -> failwithf \"Could not read %A as {type_name}: %s\" unwrapped (String.concat \", \" errors)"
+        ),
+    ]
+}
+
+///generates the functions that map every case to the json value it stands for.
+fn gen_literal_converter(cases: &[UnionCase], values: &[Value], type_name: &str) -> Vec<String> {
+    let cases = cases
+        .iter()
+        .zip(values)
+        .map(|(case, value)| {
+            format!(
+                "        JsonValue.Parse {}, {}",
+                to_fsharp_string(&value.to_string()),
+                case.name
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    vec![
+        format!("let private cases =\n    [\n{cases}\n    ]"),
+        format!(
+            "let deserialize (json : string) : {type_name} =
+    let parsed = JsonValue.Parse json
+    cases
+    |> List.tryFind (fun (value, _) -> value = parsed)
+    |> Option.map snd
+    |> Option.defaultWith (fun () -> failwithf \"Could not read %s as {type_name}\" json)"
+        ),
+        format!(
+            "let serialize (value : {type_name}) : string =
+    cases |> List.find (fun (_, case) -> case = value) |> fst |> string"
+        ),
+    ]
+}
@@ -0,0 +1,172 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use schemars::{schema::RootSchema, JsonSchema};
+
+use crate::{
+    writer::get_module_path, Config, ConfigError, Error, ExternalTypeCollector, FSharpWriter,
+    GeneratedFile, RenderCache,
+};
+
+///Generates the bindings for many types at once, meant to be used from a `build.rs`.
+///
+///```no_run
+///# #[derive(schemars::JsonSchema)]
+///# struct User {}
+///# #[derive(schemars::JsonSchema)]
+///# struct Order {}
+///type_gen::Generator::new()
+///    .add::<User>()
+///    .add::<Order>()
+///    .rerun_if_changed("../api/src")
+///    .write_to("../client/Types.fs")
+///    .unwrap();
+///```
+///
+///Types used by multiple of the added types are only generated once, and the files are ordered so
+///every type is defined before it is used.
+pub struct Generator {
+    config: Config,
+    schemas: Vec<(RootSchema, String)>,
+    rerun_if_changed: Vec<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum GeneratorError {
+    Generation(Error),
+    Config(ConfigError),
+    Io(io::Error),
+}
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GeneratorError::Generation(x) => x.fmt(f),
+            GeneratorError::Config(x) => x.fmt(f),
+            GeneratorError::Io(x) => write!(f, "Could not write the generated files: {}", x),
+        }
+    }
+}
+impl std::error::Error for GeneratorError {}
+
+impl From<Error> for GeneratorError {
+    fn from(x: Error) -> Self {
+        GeneratorError::Generation(x)
+    }
+}
+impl From<io::Error> for GeneratorError {
+    fn from(x: io::Error) -> Self {
+        GeneratorError::Io(x)
+    }
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Generator {
+    pub fn new() -> Self {
+        Self::with_config(Config::default())
+    }
+    pub fn with_config(config: Config) -> Self {
+        Self {
+            config,
+            schemas: Vec::new(),
+            rerun_if_changed: Vec::new(),
+        }
+    }
+    ///Reads the config at `path`. The build script reruns when the config changes.
+    pub fn from_config_file(path: impl AsRef<Path>) -> Result<Self, GeneratorError> {
+        let config = Config::load(path.as_ref()).map_err(GeneratorError::Config)?;
+        Ok(Self::with_config(config).rerun_if_changed(path))
+    }
+    ///Generates `A` and every type it needs.
+    pub fn add<A: JsonSchema>(self) -> Self {
+        self.add_schema(
+            schemars::schema_for!(A),
+            &get_module_path(std::any::type_name::<A>()),
+        )
+    }
+    ///Generates the type described by the schema and every type it needs.
+    ///`module_path` is the Rust module the type is from, which decides the file it ends up in
+    ///when the output is split.
+    pub fn add_schema(mut self, schema: RootSchema, module_path: &str) -> Self {
+        self.schemas.push((schema, module_path.to_owned()));
+        self
+    }
+    ///Generates every type registered with `#[type_gen::export]` or
+    ///[export_type!](crate::export_type), see [crate::exported_types].
+    #[cfg(feature = "export")]
+    pub fn add_exported(self) -> Self {
+        crate::exported_types()
+            .into_iter()
+            .fold(self, |generator, exported| {
+                generator.add_schema(exported.schema(), &exported.module_path())
+            })
+    }
+    ///Tells cargo to run the build script again when something in `path` changes, like the
+    ///source of the added types. Only has an effect when running as a build script.
+    pub fn rerun_if_changed(mut self, path: impl AsRef<Path>) -> Self {
+        self.rerun_if_changed.push(path.as_ref().to_owned());
+        self
+    }
+    ///The files, in the order they need to be compiled in.
+    pub fn files(&self) -> Result<Vec<GeneratedFile>, GeneratorError> {
+        Ok(self.build_writer(&self.config)?.files())
+    }
+    ///Writes the files and returns their paths, in the order they need to be compiled in.
+    ///
+    ///If `path` ends with `.fs` every type is written to that file, otherwise `path` is used as
+    ///the directory to write the files to.
+    pub fn write_to(self, path: impl AsRef<Path>) -> Result<Vec<PathBuf>, GeneratorError> {
+        let path = path.as_ref();
+        let mut config = self.config.clone();
+        let dir = match (path.extension(), path.file_stem()) {
+            (Some(extension), Some(name)) if extension == "fs" => {
+                config.output.split = false;
+                config.output.file_name = name.to_string_lossy().into_owned();
+                path.parent().unwrap_or_else(|| Path::new(""))
+            }
+            _ => path,
+        };
+        let writer = self.build_writer(&config)?;
+        if std::env::var_os("OUT_DIR").is_some() {
+            for path in &self.rerun_if_changed {
+                println!("cargo:rerun-if-changed={}", path.display());
+            }
+        }
+        //files that didn't change are left alone, so they don't trigger a rebuild
+        writer.write_changed_to_dir(dir, &mut RenderCache::default())?;
+        Ok(writer
+            .file_names()
+            .into_iter()
+            .map(|v| dir.join(v))
+            .collect())
+    }
+    fn build_writer(&self, config: &Config) -> Result<FSharpWriter, GeneratorError> {
+        let mut collector = ExternalTypeCollector::from_config(config);
+        let mut writer = FSharpWriter::from_config(config);
+        //every added type goes in the file of its own module, even when another type needs it first
+        for (schema, module_path) in &self.schemas {
+            if let Some(name) = schema
+                .schema
+                .metadata
+                .as_ref()
+                .and_then(|v| v.title.as_ref())
+            {
+                writer.set_module_path(name, module_path);
+            }
+        }
+        for (schema, _) in &self.schemas {
+            collector.reserve_names(schema)?;
+        }
+        for (schema, module_path) in &self.schemas {
+            writer.add_schema(schema.clone(), module_path, &mut collector)?;
+        }
+        Ok(writer)
+    }
+}
@@ -1,4 +1,8 @@
-use std::{collections::HashSet, fmt};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, io,
+    rc::Rc,
+};
 
 use indexmap::map::IndexMap;
 use schemars::{
@@ -8,69 +12,703 @@ use schemars::{
     },
     Map,
 };
-use serde_json::Value;
+use serde_json::{json, Number, Value};
+
+mod config;
+mod definition;
+#[cfg(feature = "export")]
+mod exported;
+mod fsharp_json;
+mod generator;
+mod newtonsoft;
+mod system_text_json;
+mod thoth;
+mod validation;
+mod writer;
+
+pub use config::{
+    Config, ConfigError, NamingConfig, NamingConvention, OptionalFields, OutputConfig, Serializer,
+};
+pub use definition::{Constraint, Field, Representation, TypeDefinition, TypeKind, UnionCase};
+#[cfg(feature = "export")]
+pub use exported::{exported_types, gen_exported, ExportedType};
+pub use generator::{Generator, GeneratorError};
+#[cfg(feature = "export")]
+#[doc(hidden)]
+pub use inventory;
+#[cfg(feature = "export")]
+pub use type_gen_derive::export;
+pub use writer::{FSharpWriter, FileHeader, GeneratedFile, Grouping, RenderCache, StaleFile};
 
 type Result<T> = std::result::Result<T, Error>;
 
+///Decides the names of types that don't have a name of their own,
+///like objects that are defined inside of other objects.
+pub trait NamingStrategy {
+    ///The name for the type stored in `part` of the type named `parent`.
+    ///`part` is usually the name of a field or of an enum variant.
+    fn name_nested_type(&self, parent: &str, part: &str) -> String;
+}
+
+///Joins the name of the parent and the part in PascalCase, so the type of the field `address`
+///in `User` becomes `UserAddress`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PascalCaseNaming;
+
+impl NamingStrategy for PascalCaseNaming {
+    fn name_nested_type(&self, parent: &str, part: &str) -> String {
+        format!("{}{}", parent, to_pascal_case(part))
+    }
+}
+
+///Joins the name of the parent and the part with an underscore, so the type of the field
+///`address` in `User` becomes `User_Address`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnderscoreNaming;
+
+impl NamingStrategy for UnderscoreNaming {
+    fn name_nested_type(&self, parent: &str, part: &str) -> String {
+        format!("{}_{}", parent, to_pascal_case(part))
+    }
+}
+
 #[derive(Default)]
 pub struct ExternalTypeCollector {
-    parsed_types: IndexMap<String, String>,
-    new_external_types: IndexMap<String, String>,
+    ///every type that got generated, including the root types
+    types: IndexMap<String, Rc<TypeDefinition>>,
+    ///the types in `types` that were generated as dependency and weren't handed out yet
+    new_types: Vec<String>,
     working_on: HashSet<String>,
     types_to_parse: Map<String, Schema>,
+    generic_hints: HashMap<String, (String, Vec<String>)>,
+    naming_strategy: Option<Box<dyn NamingStrategy>>,
+    name_hints: HashMap<(String, String), String>,
+    ///the names that have been given to types without a name of their own
+    claimed_names: HashSet<String>,
+    ///the schemas of the types passed to [gen], by name
+    root_schemas: HashMap<String, SchemaObject>,
+    ///types whose schema changed, which get generated again the next time they are needed
+    stale: HashSet<String>,
+    ///the named type that is currently being generated
+    owner: Option<String>,
+    ///the type that the schema of a type without a name of its own is part of
+    owners: HashMap<String, String>,
+    ///types that don't get generated, but get replaced by an existing F# type
+    type_overrides: HashMap<String, String>,
+    ///the F# type used for values with a given `format`
+    format_mappings: HashMap<String, String>,
+    ///the library the types get set up for
+    serializer: Serializer,
+    ///what fields that can be missing become
+    optional_fields: OptionalFields,
+    ///whether records get a function that checks the constraints of the schema
+    validate: bool,
+    ///whether strings and numbers with constraints get a type of their own
+    wrap_constrained: bool,
 }
 
 impl ExternalTypeCollector {
     pub fn new() -> Self {
         Default::default()
     }
+    ///Creates a collector that uses the naming, type overrides and formats of the config.
+    pub fn from_config(config: &Config) -> Self {
+        let mut collector = Self::new();
+        match config.naming.convention {
+            NamingConvention::PascalCase => collector.set_naming_strategy(PascalCaseNaming),
+            NamingConvention::Underscore => collector.set_naming_strategy(UnderscoreNaming),
+        }
+        for (parent, part, name) in config.name_hints() {
+            collector.add_name_hint(parent, part, name);
+        }
+        for (reference, type_name) in &config.type_overrides {
+            collector.add_type_override(reference, type_name);
+        }
+        for (format, type_name) in &config.formats {
+            collector.add_format_mapping(format, type_name);
+        }
+        collector.set_serializer(config.output.serializer);
+        collector.set_optional_fields(config.output.optional_fields);
+        collector.set_validate(config.output.validate);
+        collector.set_wrap_constrained(config.output.wrap_constrained);
+        collector
+    }
+    ///Uses the F# type `type_name` wherever the type `reference` is used, instead of generating
+    ///a type for it.
+    pub fn add_type_override(&mut self, reference: &str, type_name: &str) {
+        self.type_overrides
+            .insert(reference.to_owned(), type_name.to_owned());
+    }
+    ///Uses the F# type `type_name` for values with the given `format`, like `date-time`.
+    pub fn add_format_mapping(&mut self, format: &str, type_name: &str) {
+        self.format_mappings
+            .insert(format.to_owned(), type_name.to_owned());
+    }
+    ///Sets up the generated types for the given json library. Defaults to FSharp.Json.
+    ///
+    ///With Thoth.Json, types with a `.` in their name, like the ones from
+    ///[Self::add_type_override], are expected to work with the automatic coders of Thoth. Other
+    ///type overrides need a module with a `decoder` and an `encode` function.
+    pub fn set_serializer(&mut self, serializer: Serializer) {
+        self.serializer = serializer;
+    }
+    ///Sets what fields that can be missing from the json become. Defaults to `option`.
+    pub fn set_optional_fields(&mut self, optional_fields: OptionalFields) {
+        self.optional_fields = optional_fields;
+    }
+    ///Gives every record a `validate : T -> Result<T, string list>` function in its module, which
+    ///checks the lengths, patterns, ranges and amounts of items the schema requires, as well as
+    ///the values of fields that can only hold a single value.
+    pub fn set_validate(&mut self, validate: bool) {
+        self.validate = validate;
+    }
+    ///Gives fields holding a string or number with constraints, like a `pattern` or `minimum`, a
+    ///type of their own instead, like `type Email = private Email of string`. Its module has a
+    ///`create` function that checks the constraints, and reading the json goes through it as well.
+    pub fn set_wrap_constrained(&mut self, wrap_constrained: bool) {
+        self.wrap_constrained = wrap_constrained;
+    }
+    ///adds the attributes and functions the serializer needs
+    fn finish_definition(&self, mut definition: TypeDefinition) -> Result<TypeDefinition> {
+        //the type parameters are only known by now
+        if self.validate {
+            definition
+                .functions
+                .extend(validation::gen_validate(&definition));
+        }
+        match self.serializer {
+            Serializer::FSharpJson => {
+                fsharp_json::add_converters(&mut definition);
+                fsharp_json::use_nested_types(&mut definition, |name| {
+                    self.types
+                        .get(name)
+                        .filter(|_| !self.stale.contains(name))
+                        .map(AsRef::as_ref)
+                })?;
+            }
+            Serializer::Newtonsoft => newtonsoft::add_converters(&mut definition),
+            Serializer::SystemTextJson => system_text_json::add_converters(&mut definition),
+            Serializer::Thoth => {
+                let codecs = thoth::gen_codecs(&definition, &self.used_in_turn(&definition));
+                definition.functions.extend(codecs);
+            }
+        }
+        Ok(definition)
+    }
+    ///the types used by `definition` that use it in turn, directly or not. The types that are
+    ///still being generated count as using it, as they are waiting for it to be done.
+    fn used_in_turn(&self, definition: &TypeDefinition) -> HashSet<String> {
+        let by_name = self
+            .types
+            .iter()
+            .filter(|(key, _)| !self.stale.contains(*key))
+            .map(|(_, definition)| (definition.name.as_str(), definition.as_ref()))
+            .collect::<HashMap<_, _>>();
+        let leads_back = |start: &str| {
+            let mut reached = HashSet::new();
+            let mut todo = vec![start];
+            while let Some(name) = todo.pop() {
+                if name == definition.name || self.working_on.contains(name) {
+                    return true;
+                }
+                if let Some(used) = by_name.get(name).filter(|_| reached.insert(name)) {
+                    todo.extend(used.used_names());
+                }
+            }
+            false
+        };
+        definition
+            .used_names()
+            .filter(|v| *v != definition.name && leads_back(v))
+            .map(ToOwned::to_owned)
+            .collect()
+    }
     fn gen_type_and_insert(&mut self, reference: String, type_rep: &Schema) -> Result<String> {
         match type_rep {
             Schema::Bool(_) => Ok(reference),
             Schema::Object(x) => {
                 let genned_type = gen_from_schema(x, &reference, self)?;
-                self.new_external_types
-                    .insert(reference.clone(), genned_type.clone());
-                self.parsed_types.insert(reference.clone(), genned_type);
+                self.insert_new_type(reference.clone(), genned_type)?;
                 Ok(reference)
             }
         }
     }
     pub fn get_type(&mut self, reference: &str) -> Result<String> {
         let reference = remove_start_from_ref(reference);
-        if self.new_external_types.contains_key(reference)
-            || self.parsed_types.contains_key(reference)
-        {
+        //the type parameters of the generic type that is being generated
+        if reference.starts_with('\'') {
+            return Ok(reference.to_owned());
+        }
+        if let Some(type_name) = self.type_overrides.get(reference) {
+            return Ok(type_name.to_owned());
+        }
+        if let Some(generic) = self.get_generic_type(reference)? {
+            return Ok(generic);
+        }
+        if self.has_type(reference) || self.working_on.contains(reference) {
             return Ok(reference.to_owned());
         }
-        self.parsed_types
+        let x = self
+            .types_to_parse
             .get(reference)
-            .map(|v| Ok(v.clone()))
-            .unwrap_or_else(|| {
-                if self.working_on.contains(reference) {
-                    Ok(reference.to_owned())
-                } else {
-                    let x = self
-                        .types_to_parse
-                        .get(reference)
-                        .ok_or(Error::ExternalTypeNotAvailable)?
-                        .clone();
-                    let reference = reference.to_owned();
-                    self.gen_type_and_insert(reference, &x)
-                }
-            })
+            .ok_or(Error::ExternalTypeNotAvailable)?
+            .clone();
+        self.gen_type_and_insert(reference.to_owned(), &x)
+    }
+    fn has_type(&self, key: &str) -> bool {
+        self.types.contains_key(key) && !self.stale.contains(key)
+    }
+    ///stores a generated type. A type that was generated before keeps its place.
+    fn insert_new_type(&mut self, key: String, definition: TypeDefinition) -> Result<()> {
+        let definition = self.finish_definition(definition)?;
+        self.stale.remove(&key);
+        self.types.insert(key.clone(), Rc::new(definition));
+        self.new_types.push(key);
+        Ok(())
     }
     pub fn add_types_to_parse(&mut self, types: Map<String, Schema>) {
         self.types_to_parse.extend(types)
     }
-    pub fn add_unnamed_type(&mut self, prefix: &str, type_rep: &ObjectValidation) -> Result<()> {
-        let res = gen_full_object(type_rep, prefix, self)?;
-        self.new_external_types.insert(prefix.to_owned(), res);
+    ///Makes the names of the types in `schema` known before anything gets generated, so types
+    ///without a name of their own can't take them. When generating several schemas, call this
+    ///for all of them first, or a type of a later schema could find its name in use.
+    pub fn reserve_names(&mut self, schema: &RootSchema) -> Result<()> {
+        let name = get_name(&schema.schema, self)?;
+        self.claimed_names.insert(name);
+        self.add_types_to_parse(schema.definitions.clone());
         Ok(())
     }
+    ///Tells the collector that the type `reference` is the generic type `base` applied to
+    ///`arguments`, for when this can't be read from the name schemars gave it.
+    ///
+    ///The arguments use the same names as schemars, so either the name of another type or
+    ///names like `String` and `Array_of_uint32`.
+    pub fn add_generic_hint(&mut self, reference: &str, base: &str, arguments: Vec<String>) {
+        self.generic_hints
+            .insert(reference.to_owned(), (base.to_owned(), arguments));
+    }
+    ///schemars names instances of generic types like `Page_for_User`.
+    ///These are turned into a single generic type `Page<'T>` which is then used as `Page<User>`
+    fn get_generic_type(&mut self, reference: &str) -> Result<Option<String>> {
+        let (base, arguments) = match self.get_generic_parts(reference) {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+        let argument_types = match self.resolve_generic_arguments(&arguments)? {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+        if self
+            .gen_generic_definition(reference, &base, arguments.len())?
+            .is_none()
+        {
+            return Ok(None);
+        }
+        Ok(Some(format!("{}<{}>", base, argument_types.join(", "))))
+    }
+    ///generates the generic type that the root type `name` is an instance of, the same way as
+    ///when it is referenced. Returns the key it is stored under and whether it was generated just
+    ///now.
+    fn gen_generic_root(
+        &mut self,
+        name: &str,
+        schema: &SchemaObject,
+    ) -> Result<Option<(String, bool)>> {
+        let (base, arguments) = match self.get_generic_parts(name) {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+        self.types_to_parse
+            .insert(name.to_owned(), Schema::Object(schema.clone()));
+        if self.resolve_generic_arguments(&arguments)?.is_none() {
+            return Ok(None);
+        }
+        let params = gen_generic_params(arguments.len());
+        let existed = self.has_type(&format!("{}<{}>", base, params.join(", ")));
+        let key = match self.gen_generic_definition(name, &base, arguments.len())? {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+        if !existed {
+            //root types are handed out by [gen], not as dependencies
+            self.new_types.retain(|v| v != &key);
+        }
+        Ok(Some((key, !existed)))
+    }
+    fn get_generic_parts(&self, reference: &str) -> Option<(String, Vec<String>)> {
+        self.generic_hints.get(reference).cloned().or_else(|| {
+            let (base, arguments) = reference.split_once("_for_")?;
+            Some((
+                base.to_owned(),
+                arguments.split("_and_").map(ToOwned::to_owned).collect(),
+            ))
+        })
+    }
+    fn resolve_generic_arguments(&mut self, arguments: &[String]) -> Result<Option<Vec<String>>> {
+        arguments
+            .iter()
+            .map(|v| self.resolve_generic_argument(v))
+            .collect::<Result<Option<Vec<_>>>>()
+    }
+    ///turns the name schemars gave to a type into the name of the F# type
+    fn resolve_generic_argument(&mut self, argument: &str) -> Result<Option<String>> {
+        if self.types_to_parse.contains_key(argument) || self.generic_hints.contains_key(argument) {
+            return self.get_type(argument).map(Some);
+        }
+        let wrapped = [
+            ("Array_of_", "{}[]"),
+            ("Set_of_", "{}[]"),
+            ("Nullable_", "option<{}>"),
+            ("Map_of_", "Map<string,{}>"),
+        ]
+        .into_iter()
+        .find_map(|(prefix, template)| {
+            argument.strip_prefix(prefix).map(|inner| (inner, template))
+        });
+        if let Some((inner, template)) = wrapped {
+            return self
+                .resolve_generic_argument(inner)
+                .map(|v| v.map(|v| template.replace("{}", &v)));
+        }
+        Ok(match argument {
+            "String" | "Character" => Some("string".to_owned()),
+            "Boolean" => Some("bool".to_owned()),
+            "float" | "double" => Some("float".to_owned()),
+            "Null" => Some("System.ValueTuple".to_owned()),
+            "AnyValue" => Some(self.serializer.any_json_type().to_owned()),
+            x if x.trim_start_matches('u').starts_with("int") => Some("int".to_owned()),
+            _ => None,
+        })
+    }
+    ///generates the generic type that `reference` is an instance of, unless it was generated
+    ///already, and returns the key it is stored under. Returns `None` when it can't be found out
+    ///where the schema uses the type parameters.
+    fn gen_generic_definition(
+        &mut self,
+        reference: &str,
+        base: &str,
+        amount: usize,
+    ) -> Result<Option<String>> {
+        let params = gen_generic_params(amount);
+        let key = format!("{}<{}>", base, params.join(", "));
+        if self.has_type(&key) || self.working_on.contains(&key) {
+            return Ok(Some(key));
+        }
+        let schema = match self.gen_generic_schema(reference, base, &params)? {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+        self.working_on.insert(key.clone());
+        let res = gen_from_schema(&schema, base, self);
+        self.working_on.remove(&key);
+        let mut res = res?;
+        res.type_params = params;
+        self.insert_new_type(key.clone(), res)?;
+        Ok(Some(key))
+    }
+    ///the schema of an instance of the generic type `base`, with the places that use a type
+    ///parameter replaced by a reference to that parameter.
+    ///
+    ///A place uses a parameter when it changes between two instances that only differ in that
+    ///argument. Without such an instance, only references to the argument count, as an argument
+    ///like `String` can't be told apart from a field that is always a `string`.
+    fn gen_generic_schema(
+        &self,
+        reference: &str,
+        base: &str,
+        params: &[String],
+    ) -> Result<Option<SchemaObject>> {
+        let instances = self
+            .types_to_parse
+            .keys()
+            .filter_map(|key| {
+                let (other_base, arguments) = self.get_generic_parts(key)?;
+                (other_base == base && arguments.len() == params.len())
+                    .then(|| (key.to_owned(), arguments))
+            })
+            .collect::<Vec<_>>();
+        //an instance that only uses other types as arguments is preferred, as they can be found
+        //even without another instance to compare with
+        let (source, arguments) = instances
+            .iter()
+            .find(|(_, arguments)| {
+                arguments
+                    .iter()
+                    .all(|v| self.types_to_parse.contains_key(v))
+            })
+            .or_else(|| instances.iter().find(|(key, _)| key == reference))
+            .cloned()
+            .ok_or(Error::ExternalTypeNotAvailable)?;
+        let schema = match &self.types_to_parse[&source] {
+            Schema::Object(x) => serde_json::to_value(x).map_err(|_| Error::TypeIsNoRealType)?,
+            Schema::Bool(_) => return Err(Error::TypeIsNoRealType),
+        };
+        let mut places = Vec::new();
+        for (i, param) in params.iter().enumerate() {
+            let other = instances.iter().find(|(_, other)| {
+                (0..params.len()).all(|j| (other[j] == arguments[j]) == (j != i))
+            });
+            let mut found = Vec::new();
+            match other.map(|(key, _)| &self.types_to_parse[key]) {
+                Some(Schema::Object(other)) => {
+                    let other = serde_json::to_value(other).map_err(|_| Error::TypeIsNoRealType)?;
+                    find_differences(&schema, &other, String::new(), &mut found);
+                }
+                _ => {
+                    let argument = json!({ "$ref": format!("#/definitions/{}", arguments[i]) });
+                    find_equal(&schema, &argument, String::new(), &mut found);
+                }
+            }
+            if found.is_empty() {
+                return Ok(None);
+            }
+            places.extend(found.into_iter().map(|v| (v, param)));
+        }
+        let mut schema = schema;
+        for (place, param) in places {
+            if let Some(value) = schema.pointer_mut(&place) {
+                *value = json!({ "$ref": format!("#/definitions/{}", param) });
+            }
+        }
+        serde_json::from_value(schema)
+            .map(Some)
+            .map_err(|_| Error::TypeIsNoRealType)
+    }
+    ///Sets how types without a name of their own get named. Defaults to [PascalCaseNaming].
+    pub fn set_naming_strategy(&mut self, strategy: impl NamingStrategy + 'static) {
+        self.naming_strategy = Some(Box::new(strategy));
+    }
+    ///Gives the type stored in `part` of `parent` the name `name`, instead of the one the
+    ///naming strategy would pick.
+    pub fn add_name_hint(&mut self, parent: &str, part: &str, name: &str) {
+        self.name_hints
+            .insert((parent.to_owned(), part.to_owned()), name.to_owned());
+    }
+    fn name_nested_type(&self, parent: &str, part: &str) -> String {
+        self.name_hints
+            .get(&(parent.to_owned(), part.to_owned()))
+            .cloned()
+            .unwrap_or_else(|| match &self.naming_strategy {
+                Some(strategy) => strategy.name_nested_type(parent, part),
+                None => PascalCaseNaming.name_nested_type(parent, part),
+            })
+    }
+    fn is_name_taken(&self, name: &str) -> bool {
+        self.claimed_names.contains(name)
+            || self.types_to_parse.contains_key(name)
+            || self.has_type(name)
+            || self.working_on.contains(name)
+    }
+    ///reserves a name for a type without a name of its own.
+    ///If the name is already in use a number gets added to it, so no type gets overwritten.
+    fn claim_name(&mut self, name: &str) -> String {
+        let name = (1..)
+            .map(|i| match i {
+                1 => name.to_owned(),
+                i => format!("{}{}", name, i),
+            })
+            .find(|v| !self.is_name_taken(v))
+            .expect("there is always a name that is not yet used");
+        self.claimed_names.insert(name.clone());
+        name
+    }
+    ///Generates a record for an object without a name of its own and returns the name it got.
+    ///This is `prefix`, unless that name was already taken.
+    pub fn add_unnamed_type(
+        &mut self,
+        prefix: &str,
+        type_rep: &ObjectValidation,
+    ) -> Result<String> {
+        let name = self.claim_name(prefix);
+        if let Some(owner) = &self.owner {
+            self.owners.insert(name.clone(), owner.clone());
+        }
+        let old_owner = self.owner.replace(name.clone());
+        let res = gen_full_object(type_rep, &name, self);
+        self.owner = old_owner;
+        self.insert_new_type(name.clone(), res?)?;
+        Ok(name)
+    }
 
+    ///generates a union that can hold any of the given types and returns its name.
+    ///The json it represents doesn't say which case it is.
+    fn add_anonymous_union(&mut self, name: &str, types: Vec<String>) -> Result<String> {
+        let name = &self.claim_name(name);
+        if let Some(owner) = &self.owner {
+            self.owners.insert(name.clone(), owner.clone());
+        }
+        let mut seen = HashSet::new();
+        let cases = types
+            .into_iter()
+            .filter(|v| seen.insert(v.clone()))
+            .map(|v| {
+                let case_name = gen_union_case_name(&v);
+                UnionCase {
+                    name: case_name.clone(),
+                    wire_name: case_name,
+                    docs: None,
+                    deprecated: false,
+                    attributes: Vec::new(),
+                    of: Some(v),
+                }
+            })
+            .collect();
+        let definition =
+            TypeDefinition::new(name, TypeKind::Union(cases), Representation::Untagged);
+        self.insert_new_type(name.to_owned(), definition)?;
+        Ok(name.to_owned())
+    }
+
+    ///generates a type wrapping a value of the type `of`, which can only be made when it follows
+    ///the constraints, and returns its name. `field` is the field the constraints come from.
+    fn add_wrapper_type(
+        &mut self,
+        name: &str,
+        field: &str,
+        of: &str,
+        constraints: Vec<Constraint>,
+    ) -> Result<String> {
+        let name = &self.claim_name(name);
+        if let Some(owner) = &self.owner {
+            self.owners.insert(name.clone(), owner.clone());
+        }
+        let functions = validation::gen_wrapper_functions(name, of, &constraints, field);
+        let mut definition = TypeDefinition::new(
+            name,
+            TypeKind::Wrapper {
+                of: of.to_owned(),
+                constraints,
+            },
+            Representation::Untagged,
+        );
+        definition.functions = functions;
+        self.insert_new_type(name.to_owned(), definition)?;
+        Ok(name.to_owned())
+    }
+
+    ///The types that were generated as dependency since the last time this got called,
+    ///together with the F# code for them.
     pub fn get_new_external_types(&mut self) -> impl Iterator<Item = (String, String)> + '_ {
-        self.new_external_types.drain(..)
+        let types = &self.types;
+        self.new_types
+            .drain(..)
+            .map(move |key| (key.clone(), types[&key].to_string()))
+    }
+    ///The types that were generated as dependency since the last time this got called.
+    pub fn get_new_types(&mut self) -> impl Iterator<Item = Rc<TypeDefinition>> + '_ {
+        let types = &self.types;
+        self.new_types
+            .drain(..)
+            .map(move |key| Rc::clone(&types[&key]))
+    }
+    ///The type stored under `name` together with every type it uses, directly or not,
+    ///in the order they were generated in.
+    pub fn get_type_with_dependencies(&self, name: &str) -> Vec<Rc<TypeDefinition>> {
+        let keys_by_name = self
+            .types
+            .iter()
+            .filter(|(key, _)| !self.stale.contains(*key))
+            .map(|(key, definition)| (definition.name.as_str(), key.as_str()))
+            .collect::<HashMap<_, _>>();
+        let mut reached = HashSet::new();
+        let mut todo = vec![name];
+        while let Some(key) = todo.pop() {
+            let definition = match self.types.get(key) {
+                Some(x) if reached.insert(key) => x,
+                _ => continue,
+            };
+            todo.extend(
+                definition
+                    .used_names()
+                    .filter_map(|v| keys_by_name.get(v).copied()),
+            );
+        }
+        self.types
+            .iter()
+            .filter(|(key, _)| reached.contains(key.as_str()))
+            .map(|(_, definition)| Rc::clone(definition))
+            .collect()
+    }
+    ///Compares the schemas in `schema` with the ones that were used before. The types made from
+    ///schemas that changed are forgotten and get generated again by the next call to [gen],
+    ///while the rest stay as they are.
+    ///Returns the names of the types that were forgotten.
+    pub fn update_schema(&mut self, schema: &RootSchema) -> Result<Vec<String>> {
+        let mut changed = Vec::new();
+        let name = get_name(&schema.schema, self)?;
+        if let Some(old) = self.root_schemas.get(&name) {
+            if old != &schema.schema {
+                changed.push(name);
+            }
+        }
+        for (key, value) in &schema.definitions {
+            if let Some(old) = self.types_to_parse.get(key) {
+                if old != value {
+                    changed.push(key.to_owned());
+                }
+            }
+        }
+        self.add_types_to_parse(schema.definitions.clone());
+        //a changed instance of a generic type changes the generic type as well
+        for reference in changed.clone() {
+            if let Some((base, arguments)) = self.get_generic_parts(&reference) {
+                let params = gen_generic_params(arguments.len());
+                changed.push(format!("{}<{}>", base, params.join(", ")));
+            }
+        }
+        Ok(self.forget_types(changed))
+    }
+    ///generates the types from the definitions whose schema changed again, as the types using
+    ///them won't ask for them when they didn't change themselves.
+    fn regenerate_stale(&mut self) -> Result<()> {
+        let stale = self
+            .types_to_parse
+            .keys()
+            .filter(|key| {
+                self.stale.contains(*key)
+                    || self
+                        .get_generic_parts(key)
+                        .is_some_and(|(base, arguments)| {
+                            let params = gen_generic_params(arguments.len());
+                            self.stale
+                                .contains(&format!("{}<{}>", base, params.join(", ")))
+                        })
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        for key in stale {
+            self.get_type(&key)?;
+        }
+        Ok(())
+    }
+    ///marks the types as stale, together with the types without a name of their own that
+    ///they contain.
+    fn forget_types(&mut self, mut keys: Vec<String>) -> Vec<String> {
+        let mut forgotten = Vec::new();
+        while let Some(key) = keys.pop() {
+            if !self.types.contains_key(&key) || !self.stale.insert(key.clone()) {
+                continue;
+            }
+            let name = key.split('<').next().unwrap_or(&key);
+            keys.extend(
+                self.owners
+                    .iter()
+                    .filter(|(_, owner)| *owner == name)
+                    .map(|(owned, _)| owned.clone()),
+            );
+            self.claimed_names.remove(&key);
+            forgotten.push(key);
+        }
+        forgotten
+    }
+    ///Writes the types that were generated as dependency since the last time they were
+    ///requested to `out`, separated by newlines.
+    pub fn write_new_types(&mut self, out: &mut impl io::Write) -> io::Result<()> {
+        for definition in self.get_new_types() {
+            definition.write_to_io(out)?;
+            out.write_all(b"\n")?;
+        }
+        Ok(())
     }
 }
 
@@ -78,6 +716,7 @@ impl ExternalTypeCollector {
 pub enum Error {
     NoMetaDataForType,
     NoNameForType,
+    TypeCantBeNested,
     NoSubSchemaForType,
     NoObjectPartFound,
     TypeIsNoRealType,
@@ -117,6 +756,10 @@ impl fmt::Display for Error {
             Error::ExternalTypeNotAvailable => {
                 write!(f, "An external type was referenced, but it was not found")
             }
+            Error::TypeCantBeNested => write!(
+                f,
+                "The serializer can only read and write the type on its own, not inside of another type"
+            ),
             Error::SimpleEnumNotSimple => write!(
                 f,
                 "An enum was expected to not store any values, but it does"
@@ -126,6 +769,110 @@ impl fmt::Display for Error {
 }
 impl std::error::Error for Error {}
 
+fn gen_generic_params(amount: usize) -> Vec<String> {
+    match amount {
+        1 => vec!["'T".to_owned()],
+        amount => (1..=amount).map(|v| format!("'T{}", v)).collect(),
+    }
+}
+
+///the keywords of a schema that hold other schemas
+const SUBSCHEMA_KEYWORDS: [&str; 6] = [
+    "properties",
+    "items",
+    "additionalProperties",
+    "anyOf",
+    "allOf",
+    "oneOf",
+];
+
+///collects the json pointers to the schemas in `schema` that differ from the ones at the same place
+///in `other`. A schema differs as a whole when more than the schemas it holds differ.
+fn find_differences(schema: &Value, other: &Value, path: String, found: &mut Vec<String>) {
+    if schema == other {
+        return;
+    }
+    let (Value::Object(schema), Value::Object(other)) = (schema, other) else {
+        found.push(path);
+        return;
+    };
+    let (parts, other_parts) = (all_subschemas(schema), all_subschemas(other));
+    let same_shape = own_keywords(schema) == own_keywords(other)
+        && parts.len() == other_parts.len()
+        && parts
+            .iter()
+            .zip(&other_parts)
+            .all(|((a, a_parts), (b, b_parts))| {
+                a == b
+                    && a_parts.len() == b_parts.len()
+                    && a_parts.iter().zip(b_parts).all(|(a, b)| a.0 == b.0)
+            });
+    if !same_shape {
+        found.push(path);
+        return;
+    }
+    for ((keyword, parts), (_, other_parts)) in parts.into_iter().zip(other_parts) {
+        for ((key, part), (_, other_part)) in parts.into_iter().zip(other_parts) {
+            let path = format!("{}/{}{}", path, keyword, key);
+            find_differences(part, other_part, path, found);
+        }
+    }
+}
+
+///the keywords of a schema that don't hold other schemas. The title is left out, as it is the
+///name schemars gave to the instance of a generic type.
+fn own_keywords(schema: &serde_json::Map<String, Value>) -> Vec<(&String, &Value)> {
+    schema
+        .iter()
+        .filter(|(k, _)| k.as_str() != "title" && !SUBSCHEMA_KEYWORDS.contains(&k.as_str()))
+        .collect()
+}
+
+///the schemas a schema holds, by keyword.
+fn all_subschemas(
+    schema: &serde_json::Map<String, Value>,
+) -> Vec<(&'static str, Vec<(String, &Value)>)> {
+    SUBSCHEMA_KEYWORDS
+        .iter()
+        .filter_map(|&keyword| Some((keyword, subschemas_of(keyword, schema.get(keyword)?)?)))
+        .collect()
+}
+
+///collects the json pointers to the schemas in `schema` that are equal to `target`.
+fn find_equal(schema: &Value, target: &Value, path: String, found: &mut Vec<String>) {
+    if schema == target {
+        found.push(path);
+        return;
+    }
+    let Value::Object(schema) = schema else {
+        return;
+    };
+    for (keyword, parts) in all_subschemas(schema) {
+        for (key, part) in parts {
+            find_equal(part, target, format!("{}/{}{}", path, keyword, key), found);
+        }
+    }
+}
+
+///the schemas held by `keyword`, together with the rest of the json pointer to them
+fn subschemas_of<'a>(keyword: &str, value: &'a Value) -> Option<Vec<(String, &'a Value)>> {
+    match (keyword, value) {
+        ("properties", Value::Object(x)) => Some(
+            x.iter()
+                .map(|(k, v)| (format!("/{}", k.replace('~', "~0").replace('/', "~1")), v))
+                .collect(),
+        ),
+        (_, Value::Array(x)) => Some(
+            x.iter()
+                .enumerate()
+                .map(|(i, v)| (format!("/{}", i), v))
+                .collect(),
+        ),
+        (_, x @ Value::Object(_)) => Some(vec![(String::new(), x)]),
+        _ => None,
+    }
+}
+
 fn remove_start_from_ref(s: &str) -> &str {
     let mut to_remove_from_start = "#/definitions/".chars();
     let mut wrong_start = false;
@@ -146,12 +893,12 @@ fn remove_start_from_ref(s: &str) -> &str {
 }
 
 pub enum GeneratedType<'a> {
-    Generated(&'a str),
-    FromExternalTypes(&'a str),
+    Generated(&'a TypeDefinition),
+    FromExternalTypes(&'a TypeDefinition),
 }
 
 impl<'a> GeneratedType<'a> {
-    pub fn into_option(self) -> Option<&'a str> {
+    pub fn into_option(self) -> Option<&'a TypeDefinition> {
         self.into()
     }
 }
@@ -159,18 +906,18 @@ impl<'a> GeneratedType<'a> {
 impl<'a> From<GeneratedType<'a>> for String {
     fn from(x: GeneratedType<'a>) -> Self {
         match x {
-            GeneratedType::Generated(x) | GeneratedType::FromExternalTypes(x) => x.to_owned(),
+            GeneratedType::Generated(x) | GeneratedType::FromExternalTypes(x) => x.to_string(),
         }
     }
 }
-impl<'a> From<GeneratedType<'a>> for &'a str {
+impl<'a> From<GeneratedType<'a>> for &'a TypeDefinition {
     fn from(x: GeneratedType<'a>) -> Self {
         match x {
             GeneratedType::Generated(x) | GeneratedType::FromExternalTypes(x) => x,
         }
     }
 }
-impl<'a> From<GeneratedType<'a>> for Option<&'a str> {
+impl<'a> From<GeneratedType<'a>> for Option<&'a TypeDefinition> {
     fn from(x: GeneratedType<'a>) -> Self {
         match x {
             GeneratedType::Generated(x) => Some(x),
@@ -198,36 +945,105 @@ pub fn gen(a: RootSchema, x: &mut ExternalTypeCollector) -> Result<GeneratedType
 
     let name = get_name(&schema, x)?;
     x.add_types_to_parse(a.definitions);
-    let res = if x.parsed_types.contains_key(&name) {
+    x.regenerate_stale()?;
+    if let Some((key, generated)) = x.gen_generic_root(&name, &schema)? {
+        x.root_schemas.insert(name, schema);
+        return Ok(if generated {
+            GeneratedType::Generated(&x.types[&key])
+        } else {
+            GeneratedType::FromExternalTypes(&x.types[&key])
+        });
+    }
+    let res = if x.has_type(&name) {
         x.working_on.remove(&name);
-        GeneratedType::FromExternalTypes(x.parsed_types.get(&name).unwrap())
+        GeneratedType::FromExternalTypes(&x.types[&name])
     } else {
         let res = gen_from_schema(&schema, &name, x)?;
-        x.parsed_types.insert(name.clone(), res);
-        let parsed = x.parsed_types.get(&name).unwrap();
-        GeneratedType::Generated(parsed)
+        let res = x.finish_definition(res)?;
+        x.stale.remove(&name);
+        x.types.insert(name.clone(), Rc::new(res));
+        x.root_schemas.insert(name.clone(), schema);
+        GeneratedType::Generated(&x.types[&name])
     };
     Ok(res)
 }
 
-fn gen_from_schema(a: &SchemaObject, name: &str, x: &mut ExternalTypeCollector) -> Result<String> {
+fn gen_from_schema(
+    a: &SchemaObject,
+    name: &str,
+    x: &mut ExternalTypeCollector,
+) -> Result<TypeDefinition> {
     x.working_on.insert(name.to_owned());
-    let res = if should_map_to_enum(a) {
+    let old_owner = x.owner.replace(name.to_owned());
+    let literal = should_map_to_enum(a)
+        .then(|| gen_literal_type(a, name))
+        .flatten();
+    let res = if let Some(literal) = literal {
+        Ok(literal)
+    } else if should_map_to_enum(a) {
         gen_enum(a, x, Some(name), name)
     } else {
         gen_object_from_schema_object(a, name, x)
     };
+    x.owner = old_owner;
     x.working_on.remove(name);
-    res
+    res.map(|mut definition| {
+        definition.docs = get_type_docs(a);
+        definition.deprecated = is_deprecated(a);
+        definition
+    })
 }
 
 fn gen_object_from_schema_object(
     a: &SchemaObject,
     name: &str,
     x: &mut ExternalTypeCollector,
-) -> Result<String> {
-    let res = a.object.as_deref().ok_or(Error::NoObjectPartFound)?;
-    gen_full_object(res, name, x)
+) -> Result<TypeDefinition> {
+    let res = get_flattened_object(a, x)?;
+    gen_full_object(&res, name, x)
+}
+
+///merges the objects that `#[serde(flatten)]` spreads over `allOf` back into a single object.
+fn get_flattened_object(a: &SchemaObject, x: &ExternalTypeCollector) -> Result<ObjectValidation> {
+    let flattened_parts = get_flattened_parts(a);
+    if a.object.is_none() && flattened_parts.is_empty() {
+        return Err(Error::NoObjectPartFound);
+    }
+    let mut res = a.object.as_deref().cloned().unwrap_or_default();
+    for part in flattened_parts {
+        let part = match part.reference.as_deref() {
+            Some(reference) => match x.types_to_parse.get(remove_start_from_ref(reference)) {
+                Some(Schema::Object(v)) => v,
+                Some(Schema::Bool(_)) => continue,
+                None => return Err(Error::ExternalTypeNotAvailable),
+            },
+            None => part,
+        };
+        let part = get_flattened_object(part, x)?;
+        for (key, value) in part.properties {
+            res.properties.entry(key).or_insert(value);
+        }
+        res.required.extend(part.required);
+        if res.additional_properties.is_none() {
+            res.additional_properties = part.additional_properties;
+        }
+    }
+    Ok(res)
+}
+
+fn get_flattened_parts(a: &SchemaObject) -> Vec<&SchemaObject> {
+    a.subschemas
+        .as_deref()
+        .and_then(|v| v.all_of.as_ref())
+        .map(|v| {
+            v.iter()
+                .filter_map(|v| match v {
+                    Schema::Bool(_) => None,
+                    Schema::Object(x) => Some(x),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
 ///tries to get the name of a type.
@@ -247,15 +1063,15 @@ fn get_name(a: &SchemaObject, y: &mut ExternalTypeCollector) -> Result<String> {
 }
 //looks if the json conains an "anyof"
 fn should_map_to_enum(a: &SchemaObject) -> bool {
-    a.object.is_none()
+    a.object.is_none() && get_flattened_parts(a).is_empty()
 }
 fn gen_enum(
     a: &SchemaObject,
     x: &mut ExternalTypeCollector,
     name_overwrite: Option<&str>,
     type_prefix: &str,
-) -> Result<String> {
-    let header = name_overwrite
+) -> Result<TypeDefinition> {
+    let name = name_overwrite
         .map(ToOwned::to_owned)
         .map(Ok)
         .or_else(|| {
@@ -263,8 +1079,7 @@ fn gen_enum(
                 .as_ref()
                 .map(|z| build_in_types_to_name(z, &a.object, &a.array, x, type_prefix))
         })
-        .ok_or(Error::NoTypeSet)?
-        .map(|res| gen_simple_enum_header(&res))?;
+        .ok_or(Error::NoTypeSet)??;
     a.subschemas
         .as_deref()
         .and_then(|v| match v.one_of.as_ref() {
@@ -272,44 +1087,252 @@ fn gen_enum(
             None => v.any_of.as_ref(),
         })
         .map(|v| {
+            let tag = get_enum_tag(v);
             v.iter()
-                .map(|a| match a {
-                    Schema::Bool(_) => {
-                        panic!()
-                    }
-                    Schema::Object(z) => z
-                        .object
-                        .as_ref()
-                        .map(|y| {
-                            let (prop_name, schema) =
-                                y.properties.iter().next().expect("expected one property");
-                            let type_name = get_type_from_schema(
-                                schema,
-                                x,
-                                &format!("{}{}", type_prefix, prop_name),
-                            )?;
-                            Ok(format!("    | {} of {}\n", prop_name, type_name))
-                        })
-                        .or_else(|| {
-                            z.enum_values
-                                .as_ref()
-                                .map(|v| gen_simple_enum_body(v).map(|v| format!("{}\n", v)))
-                        })
-                        .ok_or(Error::NoNameForType)
-                        .and_then(|v| v),
+                .map(|a| gen_enum_case(a, tag, x, type_prefix))
+                .collect::<Result<Vec<_>>>()
+                .map(|cases| {
+                    let representation = match tag {
+                        Some(tag) => Representation::InternallyTagged {
+                            tag: tag.to_owned(),
+                        },
+                        None => Representation::ExternallyTagged,
+                    };
+                    TypeDefinition::new(&name, TypeKind::Union(cases.concat()), representation)
                 })
-                .collect::<Result<String>>()
         })
-        .or_else(|| a.enum_values.as_ref().map(|v| gen_simple_enum_body(v)))
+        .or_else(|| {
+            a.enum_values
+                .as_ref()
+                .map(|v| gen_literal_enum(v, &a.extensions, &name))
+        })
         .unwrap_or(Err(Error::EnumHasNoTypes))
-        .map(|v| format!("{}\n{}", header, v))
 }
-fn gen_simple_enum_body(a: &[Value]) -> Result<String> {
+
+fn gen_enum_case(
+    a: &Schema,
+    tag: Option<&str>,
+    x: &mut ExternalTypeCollector,
+    type_prefix: &str,
+) -> Result<Vec<UnionCase>> {
+    let z = match a {
+        Schema::Bool(_) => {
+            panic!()
+        }
+        Schema::Object(z) => z,
+    };
+    let mut cases = z
+        .object
+        .as_ref()
+        .map(|y| match tag {
+            Some(tag) => gen_tagged_enum_case(y, tag, x, type_prefix).map(|v| vec![v]),
+            None => {
+                let (prop_name, schema) =
+                    y.properties.iter().next().expect("expected one property");
+                let type_name = get_type_from_schema(
+                    schema,
+                    x,
+                    &x.name_nested_type(type_prefix, &to_case_name(prop_name)),
+                )?;
+                Ok(vec![gen_union_case(
+                    &to_case_name(prop_name),
+                    prop_name,
+                    Some(type_name),
+                )])
+            }
+        })
+        .or_else(|| z.enum_values.as_ref().map(|v| gen_simple_enum_cases(v)))
+        .ok_or(Error::NoNameForType)
+        .and_then(|v| v)?;
+    //the documentation of a variant is only known when it got a schema of its own
+    if let [case] = cases.as_mut_slice() {
+        case.docs = get_docs(z);
+        case.deprecated = is_deprecated(z);
+    }
+    Ok(cases)
+}
+
+///generates a case of an enum where the variant name is stored in a field next to its values.
+///The field holding the variant name is left out of the values, as it only holds the variant name.
+fn gen_tagged_enum_case(
+    a: &ObjectValidation,
+    tag: &str,
+    x: &mut ExternalTypeCollector,
+    type_prefix: &str,
+) -> Result<UnionCase> {
+    let wire_name = a
+        .properties
+        .get(tag)
+        .and_then(get_literal)
+        .and_then(Value::as_str)
+        .ok_or(Error::NoNameForType)?;
+    let case_name = to_case_name(wire_name);
+    let mut values = a.clone();
+    values.properties.remove(tag);
+    values.required.remove(tag);
+    if values.properties.is_empty() {
+        return Ok(gen_union_case(&case_name, wire_name, None));
+    }
+    let type_name = x.add_unnamed_type(&x.name_nested_type(type_prefix, &case_name), &values)?;
+    Ok(gen_union_case(&case_name, wire_name, Some(type_name)))
+}
+
+///finds the field that every variant of an enum uses to store which variant it is,
+///which is what `#[serde(tag = "...")]` does.
+fn get_enum_tag(variants: &[Schema]) -> Option<&str> {
+    let mut tags = variants.iter().map(|v| match v {
+        Schema::Object(SchemaObject {
+            object: Some(object),
+            ..
+        }) => object
+            .properties
+            .iter()
+            .filter(|(key, _)| object.required.contains(*key))
+            .find(|(_, value)| get_literal(value).map(Value::is_string).unwrap_or(false))
+            .map(|(key, _)| key.as_str()),
+        _ => None,
+    });
+    let first = tags.next().flatten()?;
+    if tags.all(|v| v == Some(first)) {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+///gets the value of a schema that only allows a single value, like the fields serde uses for tags.
+fn get_literal(a: &Schema) -> Option<&Value> {
+    match a {
+        Schema::Bool(_) => None,
+        Schema::Object(x) => get_schema_literal(x),
+    }
+}
+
+fn get_schema_literal(a: &SchemaObject) -> Option<&Value> {
+    a.const_value.as_ref().or(match a.enum_values.as_deref() {
+        Some([value]) => Some(value),
+        _ => None,
+    })
+}
+
+///generates a type for a schema that only allows a single value, like `{ "const": "v1" }`.
+///Rather than a union with a single case, it becomes a wrapper type that only accepts that value.
+fn gen_literal_type(a: &SchemaObject, name: &str) -> Option<TypeDefinition> {
+    let value = get_schema_literal(a).filter(|v| !v.is_null())?;
+    let of = literal_type_to_name(value)?;
+    let constraints = vec![Constraint::Const(value.clone())];
+    let functions = validation::gen_wrapper_functions(name, &of, &constraints, name);
+    let mut definition = TypeDefinition::new(
+        name,
+        TypeKind::Wrapper { of, constraints },
+        Representation::Untagged,
+    );
+    definition.functions = functions;
+    Some(definition)
+}
+
+///generates an enum that only consists of values.
+///Strings become a normal union, integers an enum and anything else a union of which every case
+///stands for one of the values.
+fn gen_literal_enum(
+    a: &[Value],
+    extensions: &Map<String, Value>,
+    type_name: &str,
+) -> Result<TypeDefinition> {
+    if a.iter().all(Value::is_string) {
+        return gen_simple_enum_cases(a).map(|cases| {
+            TypeDefinition::new(
+                type_name,
+                TypeKind::Union(cases),
+                Representation::ExternallyTagged,
+            )
+        });
+    }
+    let names = a
+        .iter()
+        .enumerate()
+        .map(|(i, value)| get_literal_case_name(value, i, extensions))
+        .collect::<Vec<_>>();
+    if a.iter().all(|v| v.is_i64() || v.is_u64()) {
+        let suffix = if a
+            .iter()
+            .all(|v| v.as_i64().and_then(|v| i32::try_from(v).ok()).is_some())
+        {
+            ""
+        } else {
+            "L"
+        };
+        let cases = names
+            .into_iter()
+            .zip(a)
+            .map(|(name, value)| (name, format!("{}{}", value, suffix)))
+            .collect();
+        return Ok(TypeDefinition::new(
+            type_name,
+            TypeKind::Enum(cases),
+            Representation::Literals(a.to_vec()),
+        ));
+    }
+    Ok(TypeDefinition::new(
+        type_name,
+        TypeKind::Union(
+            names
+                .into_iter()
+                .map(|name| UnionCase {
+                    wire_name: name.clone(),
+                    name,
+                    docs: None,
+                    deprecated: false,
+                    attributes: Vec::new(),
+                    of: None,
+                })
+                .collect(),
+        ),
+        Representation::Literals(a.to_vec()),
+    ))
+}
+
+///finds a name for a case of an enum that only consists of values.
+///The names listed in `x-enum-varnames` are used if they exist.
+fn get_literal_case_name(a: &Value, index: usize, extensions: &Map<String, Value>) -> String {
+    extensions
+        .get("x-enum-varnames")
+        .and_then(|v| v.get(index))
+        .and_then(Value::as_str)
+        .map(to_case_name)
+        .unwrap_or_else(|| match a {
+            Value::Null => "Null".to_owned(),
+            Value::Bool(true) => "True".to_owned(),
+            Value::Bool(false) => "False".to_owned(),
+            Value::Number(x) => format!("Value{}", x)
+                .replace('-', "Minus")
+                .replace('.', "_"),
+            Value::String(x) => to_case_name(x),
+            Value::Array(_) | Value::Object(_) => format!("Value{}", index),
+        })
+}
+
+fn gen_simple_enum_cases(a: &[Value]) -> Result<Vec<UnionCase>> {
+    let mut used_names = HashSet::new();
     a.iter()
         .map(|v| serde_json::from_value::<String>(v.clone()))
         .collect::<std::result::Result<Vec<_>, _>>()
         .map_err(|_| Error::SimpleEnumNotSimple)
-        .map(|v| format!("    | {}", v.join("\n    | ")))
+        .map(|v| {
+            v.iter()
+                .map(|wire_name| {
+                    let case_name = to_case_name(wire_name);
+                    let case_name = (1..)
+                        .map(|i| match i {
+                            1 => case_name.clone(),
+                            i => format!("{}{}", case_name, i),
+                        })
+                        .find(|v| used_names.insert(v.clone()))
+                        .expect("there is always a name that is not yet used");
+                    gen_union_case(&case_name, wire_name, None)
+                })
+                .collect()
+        })
 }
 
 fn get_type_from_schema(
@@ -319,14 +1342,24 @@ fn get_type_from_schema(
 ) -> Result<String> {
     match a {
         Schema::Bool(_) => Err(Error::TypeIsNoRealType),
-        Schema::Object(x) => x
-            .instance_type
-            .as_ref()
-            .map(|v| build_in_types_to_name(v, &x.object, &x.array, d, type_prefix))
+        Schema::Object(x) => get_format_type(x, d)
+            .map(Ok)
+            .or_else(|| {
+                x.instance_type
+                    .as_ref()
+                    .map(|v| build_in_types_to_name(v, &x.object, &x.array, d, type_prefix))
+            })
             .or_else(|| {
                 let x = x.reference.as_deref().map(|v| d.get_type(v));
                 x
             })
+            //schemars wraps a reference in `allOf` when it needs to add documentation to it
+            .or_else(
+                || match x.subschemas.as_deref().and_then(|v| v.all_of.as_deref()) {
+                    Some([single]) => Some(get_type_from_schema(single, d, type_prefix)),
+                    _ => None,
+                },
+            )
             .or_else(|| {
                 x.subschemas.as_deref().and_then(|v| {
                     v.any_of
@@ -334,42 +1367,78 @@ fn get_type_from_schema(
                         .map(|v| convert_any_to_known_type(v, d, type_prefix))
                 })
             })
+            .or_else(|| get_literal(a).and_then(literal_type_to_name).map(Ok))
             .unwrap_or(Err(Error::NoTypeSet)),
     }
 }
 
+///gets the type that the `format` of the schema is mapped to, if it is mapped.
+fn get_format_type(a: &SchemaObject, x: &ExternalTypeCollector) -> Option<String> {
+    let type_name = x.format_mappings.get(a.format.as_ref()?)?;
+    let nullable = match &a.instance_type {
+        Some(SingleOrVec::Vec(types)) => types.contains(&InstanceType::Null),
+        _ => false,
+    };
+    Some(if nullable {
+        make_type_optional(type_name)
+    } else {
+        type_name.to_owned()
+    })
+}
+
+fn literal_type_to_name(a: &Value) -> Option<String> {
+    match a {
+        Value::Null => Some("System.ValueTuple".to_owned()),
+        Value::Bool(_) => Some("bool".to_owned()),
+        Value::Number(x) if x.is_f64() => Some("float".to_owned()),
+        Value::Number(_) => Some("int".to_owned()),
+        Value::String(_) => Some("string".to_owned()),
+        //an array can only be typed when all of its items have the same type
+        Value::Array(x) => {
+            let mut types = x.iter().map(literal_type_to_name);
+            let first = types.next().flatten()?;
+            types
+                .all(|v| v.as_ref() == Some(&first))
+                .then(|| format!("{}[]", first))
+        }
+        Value::Object(_) => None,
+    }
+}
+
 fn convert_any_to_known_type(
     v: &[Schema],
     x: &mut ExternalTypeCollector,
     type_prefix: &str,
 ) -> Result<String> {
-    if v.len() == 2 {
-        let without_null: Vec<_> = v
+    let (nulls, without_null): (Vec<_>, Vec<_>) = v.iter().partition(|v| match v {
+        Schema::Bool(_) => false,
+        Schema::Object(x) => x
+            .instance_type
+            .as_ref()
+            .map(|v| match v {
+                SingleOrVec::Single(v) => **v == InstanceType::Null,
+                SingleOrVec::Vec(_) => false,
+            })
+            .unwrap_or(false),
+    });
+    let res = match without_null.as_slice() {
+        [] => Err(Error::NoNameForType),
+        [single] => get_type_from_schema(single, x, type_prefix),
+        _ => without_null
             .iter()
-            .filter(|v| match v {
-                Schema::Bool(_) => true,
-                Schema::Object(x) => x
-                    .instance_type
-                    .as_ref()
-                    .map(|v| match v {
-                        SingleOrVec::Single(v) => **v != InstanceType::Null,
-                        SingleOrVec::Vec(_) => true,
-                    })
-                    .unwrap_or(true),
+            .enumerate()
+            .map(|(i, v)| {
+                let prefix = x.name_nested_type(type_prefix, &format!("Case{}", i + 1));
+                get_type_from_schema(v, x, &prefix)
             })
-            .collect();
-        if without_null.len() == 1 {
-            return get_type_from_schema(without_null[0], x, type_prefix)
-                .map(|v| make_type_optional(&v));
-        } else {
-            return v
-                .iter()
-                .map(|v| get_type_from_schema(v, x, type_prefix))
-                .collect::<Result<Vec<_>>>()
-                .map(|v| format!("result<{}>", v.join(",")));
-        }
-    }
-    Err(Error::NoNameForType)
+            .collect::<Result<Vec<_>>>()
+            .and_then(|v| x.add_anonymous_union(type_prefix, v)),
+    }?;
+    Ok(if nulls.is_empty() {
+        res
+    } else {
+        make_type_optional(&res)
+    })
 }
 
 fn build_in_types_to_name(
@@ -389,6 +1458,62 @@ fn make_type_optional(a: &str) -> String {
     format!("option<{}>", a)
 }
 
+///whether values of the type can get a wrapper type with constraints.
+fn is_wrappable(a: &str) -> bool {
+    matches!(a, "string" | "int" | "float")
+}
+
+///whether the type is an `option`, rather than something like an array of them.
+fn is_optional(a: &str) -> bool {
+    split_optional(a).is_some()
+}
+
+///splits a type like `option<int>` or `voption<int>` into the kind of option and the type it holds.
+fn split_optional(a: &str) -> Option<(&str, &str)> {
+    let (kind, inner) = a.split_once('<')?;
+    if kind != "option" && kind != "voption" {
+        return None;
+    }
+    let mut depth = 1;
+    let closes_at_end = inner.char_indices().all(|(i, chara)| {
+        match chara {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            _ => {}
+        }
+        depth > 0 || i == inner.len() - 1
+    });
+    (closes_at_end && depth == 0).then(|| (kind, &inner[..inner.len() - 1]))
+}
+
+///indents every line of the F# code by one level
+pub(crate) fn indent(a: &str) -> String {
+    a.lines()
+        .map(|v| format!("    {}", v))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+///splits on `separator`, skipping the ones inside of type arguments
+pub(crate) fn split_top_level<'b>(type_name: &'b str, separator: &str) -> Vec<&'b str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, chara) in type_name.char_indices() {
+        match chara {
+            '<' | '(' => depth += 1,
+            '>' | ')' => depth -= 1,
+            _ if depth == 0 && type_name[i..].starts_with(separator) && i >= start => {
+                parts.push(type_name[start..i].trim());
+                start = i + separator.len();
+            }
+            _ => {}
+        }
+    }
+    parts.push(type_name[start..].trim());
+    parts
+}
+
 fn build_in_types_from_multiple(
     a: &[InstanceType],
     v: &Option<Box<ObjectValidation>>,
@@ -396,16 +1521,23 @@ fn build_in_types_from_multiple(
     x: &mut ExternalTypeCollector,
     type_prefix: &str,
 ) -> Result<String> {
-    if a.len() == 2 {
-        let without_null: Vec<_> = a.iter().filter(|v| v != &&InstanceType::Null).collect();
-        if without_null.len() == 1 {
-            return singular_build_in_type_to_name(without_null[0], v, y, x, type_prefix)
-                .map(|v| make_type_optional(&v));
-        }
+    let without_null: Vec<_> = a.iter().filter(|v| v != &&InstanceType::Null).collect();
+    if without_null.is_empty() {
+        return singular_build_in_type_to_name(&InstanceType::Null, v, y, x, type_prefix);
     }
-    a.iter()
-        .map(|a| singular_build_in_type_to_name(a, v, y, x, type_prefix))
-        .collect()
+    let res = match without_null.as_slice() {
+        [single] => singular_build_in_type_to_name(single, v, y, x, type_prefix),
+        _ => without_null
+            .iter()
+            .map(|a| singular_build_in_type_to_name(a, v, y, x, type_prefix))
+            .collect::<Result<Vec<_>>>()
+            .and_then(|v| x.add_anonymous_union(type_prefix, v)),
+    }?;
+    Ok(if without_null.len() == a.len() {
+        res
+    } else {
+        make_type_optional(&res)
+    })
 }
 
 fn singular_build_in_type_to_name(
@@ -432,10 +1564,7 @@ fn singular_build_in_type_to_name(
                             Err(x) => Some(Err(x)),
                         }
                     })
-                    .unwrap_or_else(|| {
-                        x.add_unnamed_type(type_prefix, v)?;
-                        Ok(type_prefix.to_owned())
-                    })
+                    .unwrap_or_else(|| x.add_unnamed_type(type_prefix, v))
             })
             .unwrap_or_else(|| Ok("object".to_string()))?,
         InstanceType::Array => y
@@ -469,44 +1598,255 @@ fn gen_full_object(
     a: &ObjectValidation,
     type_name: &str,
     x: &mut ExternalTypeCollector,
-) -> Result<String> {
-    let body = get_object_body(a, x, type_name)?;
-    Ok(format!(
-        "{}    {{\n{}\n    }}",
-        gen_object_header(type_name),
-        body
+) -> Result<TypeDefinition> {
+    let parts = get_object_parts(a, x, type_name)?;
+    let representation = Representation::Object {
+        extra_fields: parts.extra_fields.is_some(),
+    };
+    Ok(TypeDefinition::new(
+        type_name,
+        TypeKind::Record(parts.fields),
+        representation,
     ))
 }
 
-fn gen_simple_enum_header(type_name: &str) -> String {
-    format!("type {} = \n", type_name)
+///turns a type like `option<string>[]` into a name that can be used as a union case, like `OptionStringArray`
+fn gen_union_case_name(type_name: &str) -> String {
+    to_pascal_case(&type_name.replace("[]", " Array"))
 }
 
-fn gen_object_header(type_name: &str) -> String {
-    gen_simple_enum_header(type_name)
+///turns a name like `in-progress` or `snake_case` into `InProgress` and `SnakeCase`
+fn to_pascal_case(a: &str) -> String {
+    a.split(|v: char| !v.is_alphanumeric())
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            let mut chars = v.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
 }
 
-fn get_object_body(
-    a: &ObjectValidation,
-    x: &mut ExternalTypeCollector,
-    type_prefix: &str,
-) -> Result<String> {
-    //panic!("should we get here? {:?}", a);
-    Ok(get_object_parts(a, x, type_prefix)?
-        .into_iter()
-        .map(|(key_name, of_type)| format!("        {} : {}", key_name, of_type))
-        .collect::<Vec<_>>()
-        .join("\n"))
+///turns a value as it is used in json into a name that F# accepts for a union case
+fn to_case_name(a: &str) -> String {
+    let name = to_pascal_case(a);
+    match name.chars().next() {
+        None => "Empty".to_owned(),
+        Some(x) if x.is_alphabetic() => name,
+        Some(_) => format!("Value{}", name),
+    }
+}
+
+///generates a union case that is called `wire_name` in json.
+fn gen_union_case(case_name: &str, wire_name: &str, of: Option<String>) -> UnionCase {
+    UnionCase {
+        name: case_name.to_owned(),
+        wire_name: wire_name.to_owned(),
+        docs: None,
+        deprecated: false,
+        attributes: Vec::new(),
+        of,
+    }
 }
+
+///The name of the field that gets every property not described by the schema.
+///This is where flattened maps end up.
+const EXTRA_FIELDS_NAME: &str = "additional_properties";
+///The type FSharp.Json and Thoth use for properties of which we don't know anything about the value
+const ANY_JSON_VALUE: &str = "JsonValue";
+
+struct ObjectParts {
+    fields: Vec<Field>,
+    ///the type of the values stored in the extra fields, if the object has any.
+    extra_fields: Option<String>,
+}
+
 fn get_object_parts(
     a: &ObjectValidation,
     x: &mut ExternalTypeCollector,
     type_prefix: &str,
-) -> Result<Vec<(String, String)>> {
-    a.properties
+) -> Result<ObjectParts> {
+    let mut fields = a
+        .properties
         .iter()
         .map(|(key, value)| {
-            get_type_from_schema(value, x, type_prefix).map(|v| (key.to_owned(), v))
+            let prefix = x.name_nested_type(type_prefix, key);
+            let schema = match value {
+                Schema::Object(value) => Some(value),
+                Schema::Bool(_) => None,
+            };
+            let type_name = get_type_from_schema(value, x, &prefix)?;
+            let mut constraints = schema.map(get_constraints).unwrap_or_default();
+            let type_name = match split_optional(&type_name) {
+                _ if !x.wrap_constrained || constraints.is_empty() => type_name,
+                Some((kind, inner)) if is_wrappable(inner) => {
+                    let wrapper =
+                        x.add_wrapper_type(&prefix, key, inner, std::mem::take(&mut constraints))?;
+                    format!("{}<{}>", kind, wrapper)
+                }
+                None if is_wrappable(&type_name) => {
+                    x.add_wrapper_type(&prefix, key, &type_name, std::mem::take(&mut constraints))?
+                }
+                _ => type_name,
+            };
+            //serde leaves fields with a default out of `required`, they can be missing
+            let default = schema
+                .and_then(|v| v.metadata.as_deref())
+                .and_then(|v| v.default.as_ref())
+                .filter(|v| x.serializer.fills_default(v, &type_name));
+            let (type_name, default, skip_if_none) = match (x.optional_fields, default) {
+                _ if a.required.contains(key) || is_optional(&type_name) => {
+                    (type_name, None, false)
+                }
+                (OptionalFields::Default, Some(default)) => {
+                    (type_name, Some(default.clone()), false)
+                }
+                (OptionalFields::ValueOption, _) if x.serializer.supports_value_options() => {
+                    (format!("voption<{}>", type_name), None, true)
+                }
+                _ => (make_type_optional(&type_name), None, true),
+            };
+            Ok(Field {
+                name: key.to_owned(),
+                type_name,
+                default,
+                skip_if_none,
+                constraints,
+                docs: schema.and_then(get_docs),
+                deprecated: schema.map(is_deprecated).unwrap_or(false),
+                attributes: Vec::new(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let extra_fields = a
+        .additional_properties
+        .as_deref()
+        .map(|v| match v {
+            Schema::Bool(false) => Ok(None),
+            Schema::Bool(true) => Ok(Some(x.serializer.any_json_type().to_owned())),
+            Schema::Object(_) => {
+                let prefix = x.name_nested_type(type_prefix, EXTRA_FIELDS_NAME);
+                get_type_from_schema(v, x, &prefix).map(Some)
+            }
         })
-        .collect::<Result<Vec<(String, String)>>>()
+        .transpose()?
+        .flatten();
+    if let Some(extra_fields) = &extra_fields {
+        fields.push(Field {
+            name: EXTRA_FIELDS_NAME.to_owned(),
+            type_name: format!("Map<string,{}>", extra_fields),
+            default: None,
+            skip_if_none: false,
+            constraints: Vec::new(),
+            docs: None,
+            deprecated: false,
+            attributes: Vec::new(),
+        });
+    }
+    Ok(ObjectParts {
+        fields,
+        extra_fields,
+    })
+}
+
+///the documentation of a type. Its title is left out, as schemars uses that for the name.
+fn get_type_docs(a: &SchemaObject) -> Option<String> {
+    a.metadata.as_deref().and_then(|v| v.description.clone())
+}
+
+///the documentation of a field or variant, which schemars takes from the doc comments.
+fn get_docs(a: &SchemaObject) -> Option<String> {
+    let metadata = a.metadata.as_deref()?;
+    let docs = [&metadata.title, &metadata.description]
+        .into_iter()
+        .flatten()
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .collect::<Vec<_>>();
+    if docs.is_empty() {
+        None
+    } else {
+        Some(docs.join("\n\n"))
+    }
+}
+
+///the requirements of the schema on the value, beyond its type.
+fn get_constraints(a: &SchemaObject) -> Vec<Constraint> {
+    //schemars stores every bound as a float, even the ones of integers
+    let to_number = |v: f64| match v.fract() == 0.0 && v.abs() < i64::MAX as f64 {
+        true => Some(Number::from(v as i64)),
+        false => Number::from_f64(v),
+    };
+    let string = a.string.as_deref();
+    let number = a.number.as_deref();
+    let array = a.array.as_deref();
+    [
+        get_schema_literal(a)
+            .filter(|v| !v.is_null())
+            .cloned()
+            .map(Constraint::Const),
+        string.and_then(|v| v.min_length).map(Constraint::MinLength),
+        string.and_then(|v| v.max_length).map(Constraint::MaxLength),
+        string
+            .and_then(|v| v.pattern.clone())
+            .map(Constraint::Pattern),
+        number
+            .and_then(|v| v.minimum)
+            .and_then(to_number)
+            .map(Constraint::Minimum),
+        number
+            .and_then(|v| v.maximum)
+            .and_then(to_number)
+            .map(Constraint::Maximum),
+        number
+            .and_then(|v| v.exclusive_minimum)
+            .and_then(to_number)
+            .map(Constraint::ExclusiveMinimum),
+        number
+            .and_then(|v| v.exclusive_maximum)
+            .and_then(to_number)
+            .map(Constraint::ExclusiveMaximum),
+        array.and_then(|v| v.min_items).map(Constraint::MinItems),
+        array.and_then(|v| v.max_items).map(Constraint::MaxItems),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+///whether the schema is marked as deprecated, which schemars does for `#[deprecated]`.
+fn is_deprecated(a: &SchemaObject) -> bool {
+    a.metadata.as_deref().map(|v| v.deprecated).unwrap_or(false)
+}
+
+fn to_fsharp_string(a: &str) -> String {
+    serde_json::to_string(a).expect("strings can always be serialized")
+}
+
+///the value as an F# expression of the type `type_name`, so `5` becomes `5.0` for a `float`.
+///Values that don't fit the type, or can't be written as an expression, give `None`.
+fn to_fsharp_value(a: &Value, type_name: &str) -> Option<String> {
+    match (a, type_name) {
+        (Value::Bool(x), "bool") => Some(x.to_string()),
+        (Value::Number(x), "int") if !x.is_f64() => Some(x.to_string()),
+        (Value::Number(x), "float") => x.as_f64().map(|v| format!("{:?}", v)),
+        (Value::String(x), "string") => Some(to_fsharp_string(x)),
+        (Value::Array(x), _) => {
+            let inner = type_name.strip_suffix("[]")?;
+            x.iter()
+                .map(|v| to_fsharp_value(v, inner))
+                .collect::<Option<Vec<_>>>()
+                .map(|v| format!("[|{}|]", v.join("; ")))
+        }
+        _ => None,
+    }
+}
+
+fn to_fsharp_literal(a: &Value) -> String {
+    match a {
+        Value::Null => "()".to_owned(),
+        x => x.to_string(),
+    }
 }
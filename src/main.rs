@@ -0,0 +1,407 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use indexmap::IndexMap;
+use schemars::schema::RootSchema;
+use type_gen::{
+    Config, ExternalTypeCollector, FSharpWriter, OptionalFields, RenderCache, Serializer,
+};
+
+const USAGE: &str = "Generates types from JSON schema files.
+
+Usage: type_gen [OPTIONS] <INPUT>...
+
+Arguments:
+  <INPUT>...                JSON schema files, or directories containing them
+
+Options:
+  -l, --lang <LANG>         The language to generate [default: fsharp] [possible values: fsharp]
+  -o, --out <DIR>           The directory to write the files to. Writes to stdout if not set
+  -c, --config <PATH>       The config file to use. Defaults to type_gen.toml or type_gen.json,
+                            if there is one. The options below overrule the config
+      --namespace <NAME>    Start every file with `namespace NAME` [default: Types]
+      --module <NAME>       Start every file with `module NAME` instead of a namespace
+      --open <NAMESPACE>    An extra namespace to open in every file. Can be repeated
+      --split               Write a file for every schema, instead of a single file
+      --file-name <NAME>    The name of the file when everything ends up in one [default: Types]
+      --serializer <NAME>   The json library the types are for [default: fsharp-json]
+                            [possible values: fsharp-json, newtonsoft, system-text-json, thoth]
+      --optional-fields <KIND>
+                            What fields that can be missing from the json become
+                            [default: option] [possible values: option, voption, default]
+      --validate            Give every record a `validate` function that checks the constraints
+                            of the schema, like lengths and ranges
+      --wrap-constrained    Give strings and numbers with constraints a type of their own, which
+                            can only be made through a `create` function checking them
+      --check               Don't write anything, but fail with a diff if the files in the
+                            output directory are not up to date. Requires --out
+      --watch               Keep running and regenerate the files when a schema changes.
+                            Requires --out
+      --poll-interval <MS>  How often --watch looks for changes, in milliseconds [default: 500]
+  -h, --help                Print this message";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Language {
+    FSharp,
+}
+
+struct Options {
+    inputs: Vec<PathBuf>,
+    language: Language,
+    out: Option<PathBuf>,
+    config: Option<PathBuf>,
+    namespace: Option<String>,
+    module: Option<String>,
+    opens: Vec<String>,
+    split: bool,
+    file_name: Option<String>,
+    serializer: Option<Serializer>,
+    optional_fields: Option<OptionalFields>,
+    validate: bool,
+    wrap_constrained: bool,
+    check: bool,
+    watch: bool,
+    poll_interval: Duration,
+}
+
+enum Command {
+    Help,
+    Generate(Options),
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut options = Options {
+        inputs: Vec::new(),
+        language: Language::FSharp,
+        out: None,
+        config: None,
+        namespace: None,
+        module: None,
+        opens: Vec::new(),
+        split: false,
+        file_name: None,
+        serializer: None,
+        optional_fields: None,
+        validate: false,
+        wrap_constrained: false,
+        check: false,
+        watch: false,
+        poll_interval: Duration::from_millis(500),
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("{} expects a value", name))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-l" | "--lang" => {
+                options.language = match value(&arg)?.to_lowercase().as_str() {
+                    "fsharp" | "f#" => Language::FSharp,
+                    x => return Err(format!("Unsupported language `{}`", x)),
+                }
+            }
+            "-o" | "--out" => options.out = Some(value(&arg)?.into()),
+            "-c" | "--config" => options.config = Some(value(&arg)?.into()),
+            "--namespace" => options.namespace = Some(value(&arg)?),
+            "--module" => options.module = Some(value(&arg)?),
+            "--open" => options.opens.push(value(&arg)?),
+            "--split" => options.split = true,
+            "--file-name" => options.file_name = Some(value(&arg)?),
+            "--serializer" => {
+                let name = value(&arg)?;
+                options.serializer = Some(
+                    Serializer::from_name(&name)
+                        .ok_or_else(|| format!("Unsupported serializer `{}`", name))?,
+                )
+            }
+            "--optional-fields" => {
+                let kind = value(&arg)?;
+                options.optional_fields = Some(
+                    OptionalFields::from_name(&kind)
+                        .ok_or_else(|| format!("Unsupported kind of optional fields `{}`", kind))?,
+                )
+            }
+            "--validate" => options.validate = true,
+            "--wrap-constrained" => options.wrap_constrained = true,
+            "--check" => options.check = true,
+            "--watch" => options.watch = true,
+            "--poll-interval" => {
+                let interval = value(&arg)?;
+                options.poll_interval = interval
+                    .parse()
+                    .map(Duration::from_millis)
+                    .map_err(|_| format!("`{}` is not a valid amount of milliseconds", interval))?
+            }
+            x if x.starts_with('-') && x != "-" => return Err(format!("Unknown option `{}`", x)),
+            _ => options.inputs.push(arg.into()),
+        }
+    }
+    if options.inputs.is_empty() {
+        return Err("No input files given".to_owned());
+    }
+    if options.check && options.out.is_none() {
+        return Err("--check needs the directory to check, given with --out".to_owned());
+    }
+    if options.watch && options.out.is_none() {
+        return Err("--watch needs the directory to write to, given with --out".to_owned());
+    }
+    if options.watch && options.check {
+        return Err("--watch and --check can't be used together".to_owned());
+    }
+    if options.namespace.is_some() && options.module.is_some() {
+        return Err("--namespace and --module can't be used together".to_owned());
+    }
+    Ok(Command::Generate(options))
+}
+
+///reads the config file and applies the options that overrule it.
+fn load_config(options: &Options) -> Result<Config, String> {
+    let path = options.config.clone().or_else(|| {
+        ["type_gen.toml", "type_gen.json"]
+            .into_iter()
+            .map(PathBuf::from)
+            .find(|v| v.is_file())
+    });
+    let mut config = match path {
+        Some(path) => Config::load(&path).map_err(|e| format!("{}: {}", path.display(), e))?,
+        None => Config::default(),
+    };
+    if let Some(namespace) = &options.namespace {
+        config.output.namespace = Some(namespace.to_owned());
+        config.output.module = None;
+    }
+    if let Some(module) = &options.module {
+        config.output.module = Some(module.to_owned());
+        config.output.namespace = None;
+    }
+    config.output.opens.extend(options.opens.iter().cloned());
+    config.output.split |= options.split;
+    config.output.validate |= options.validate;
+    config.output.wrap_constrained |= options.wrap_constrained;
+    if let Some(serializer) = options.serializer {
+        config.output.serializer = serializer;
+    }
+    if let Some(optional_fields) = options.optional_fields {
+        config.output.optional_fields = optional_fields;
+    }
+    if let Some(file_name) = &options.file_name {
+        config.output.file_name = file_name.to_owned();
+    }
+    Ok(config)
+}
+
+///turns the inputs into a list of schema files, going through the directories.
+fn collect_schema_files(inputs: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for input in inputs {
+        if input.is_dir() {
+            let mut entries = fs::read_dir(input)?
+                .map(|v| v.map(|v| v.path()))
+                .collect::<io::Result<Vec<_>>>()?;
+            entries.sort();
+            let (dirs, schemas): (Vec<_>, Vec<_>) = entries
+                .into_iter()
+                .filter(|v| v.is_dir() || v.extension().map(|v| v == "json").unwrap_or(false))
+                .partition(|v| v.is_dir());
+            files.extend(schemas);
+            files.extend(collect_schema_files(&dirs)?);
+        } else {
+            files.push(input.to_owned());
+        }
+    }
+    Ok(files)
+}
+
+fn read_schema(path: &Path) -> Result<RootSchema, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| format!("{} is not a valid JSON schema: {}", path.display(), e))
+}
+
+fn load_schemas(inputs: &[PathBuf]) -> Result<Vec<(PathBuf, RootSchema)>, String> {
+    collect_schema_files(inputs)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|file| read_schema(&file).map(|schema| (file, schema)))
+        .collect()
+}
+
+fn build_writer(
+    options: &Options,
+    config: &Config,
+    schemas: &[(PathBuf, RootSchema)],
+    collector: &mut ExternalTypeCollector,
+) -> Result<FSharpWriter, String> {
+    //F# is the only language there is a writer for, so far
+    let Language::FSharp = options.language;
+    let mut writer = FSharpWriter::from_config(config);
+    for (file, schema) in schemas {
+        collector
+            .reserve_names(schema)
+            .map_err(|e| format!("Could not generate types for {}: {}", file.display(), e))?;
+    }
+    for (file, schema) in schemas {
+        //the name of the schema file is used as module path, so when splitting the output
+        //every schema gets its own file
+        let module_path = file
+            .file_stem()
+            .map(|v| v.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        writer
+            .add_schema(schema.clone(), &module_path, collector)
+            .map_err(|e| format!("Could not generate types for {}: {}", file.display(), e))?;
+    }
+    Ok(writer)
+}
+
+fn run(options: Options) -> Result<ExitCode, String> {
+    let config = load_config(&options)?;
+    let schemas = load_schemas(&options.inputs)?;
+    let mut collector = ExternalTypeCollector::from_config(&config);
+    let writer = build_writer(&options, &config, &schemas, &mut collector)?;
+    match &options.out {
+        Some(dir) if options.check => {
+            let stale = writer
+                .check_dir(dir)
+                .map_err(|e| format!("Could not read {}: {}", dir.display(), e))?;
+            if !stale.is_empty() {
+                for file in &stale {
+                    print!("{}", file.diff);
+                }
+                eprintln!(
+                    "{} generated file(s) are out of date, run type_gen without --check to update them",
+                    stale.len()
+                );
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Some(dir) => {
+            let paths = writer
+                .write_to_dir(dir)
+                .map_err(|e| format!("Could not write to {}: {}", dir.display(), e))?;
+            for path in paths {
+                eprintln!("Wrote {}", path.display());
+            }
+        }
+        None => {
+            let files = writer.files();
+            let mut stdout = io::stdout().lock();
+            for file in &files {
+                if files.len() > 1 {
+                    writeln!(stdout, "// {}", file.name).map_err(|e| e.to_string())?;
+                }
+                write!(stdout, "{}", file.contents).map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+///A schema file as it was last read by watch mode.
+struct WatchedSchema {
+    modified: SystemTime,
+    schema: RootSchema,
+}
+
+///Reads the schema files again if they changed since the last time, and tells the collector about
+///it. Returns if anything changed.
+fn refresh_schemas(
+    options: &Options,
+    watched: &mut IndexMap<PathBuf, WatchedSchema>,
+    collector: &mut ExternalTypeCollector,
+) -> Result<bool, String> {
+    let files = collect_schema_files(&options.inputs).map_err(|e| e.to_string())?;
+    let old_len = watched.len();
+    watched.retain(|path, _| files.contains(path));
+    let mut changed = watched.len() != old_len;
+    for file in &files {
+        let modified = fs::metadata(file)
+            .and_then(|v| v.modified())
+            .map_err(|e| format!("Could not read {}: {}", file.display(), e))?;
+        if watched.get(file).map(|v| v.modified) == Some(modified) {
+            continue;
+        }
+        let schema = read_schema(file)?;
+        let forgotten = collector
+            .update_schema(&schema)
+            .map_err(|e| format!("Could not read {}: {}", file.display(), e))?;
+        if !forgotten.is_empty() {
+            eprintln!("{} changed: {}", file.display(), forgotten.join(", "));
+        }
+        watched.insert(file.to_owned(), WatchedSchema { modified, schema });
+        changed = true;
+    }
+    //keeps the files in the same order as a normal run would use
+    let position = |path: &PathBuf| files.iter().position(|v| v == path);
+    watched.sort_by(|a, _, b, _| position(a).cmp(&position(b)));
+    Ok(changed)
+}
+
+///Regenerates the files in `dir` every time a schema changes. Only the types whose schema changed
+///get generated and rendered again and only the files whose contents changed get written.
+fn watch(options: &Options, config: &Config, dir: &Path, interval: Duration) -> ! {
+    let mut collector = ExternalTypeCollector::from_config(config);
+    let mut cache = RenderCache::default();
+    let mut watched = IndexMap::new();
+    loop {
+        let res = refresh_schemas(options, &mut watched, &mut collector).and_then(|changed| {
+            if !changed {
+                return Ok(());
+            }
+            let schemas = watched
+                .iter()
+                .map(|(path, v)| (path.to_owned(), v.schema.clone()))
+                .collect::<Vec<_>>();
+            let paths = build_writer(options, config, &schemas, &mut collector)?
+                .write_changed_to_dir(dir, &mut cache)
+                .map_err(|e| format!("Could not write to {}: {}", dir.display(), e))?;
+            for path in paths {
+                eprintln!("Wrote {}", path.display());
+            }
+            Ok(())
+        });
+        if let Err(e) = res {
+            eprintln!("error: {}", e);
+        }
+        thread::sleep(interval);
+    }
+}
+
+fn main() -> ExitCode {
+    match parse_args(std::env::args().skip(1)) {
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        Ok(Command::Generate(options)) if options.watch => match load_config(&options) {
+            Ok(config) => {
+                let dir = options.out.clone().expect("--watch requires --out");
+                watch(&options, &config, &dir, options.poll_interval)
+            }
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ExitCode::FAILURE
+            }
+        },
+        Ok(Command::Generate(options)) => match run(options) {
+            Ok(code) => code,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ExitCode::FAILURE
+            }
+        },
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
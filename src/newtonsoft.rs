@@ -0,0 +1,352 @@
+use serde_json::Value;
+
+use crate::{
+    split_top_level, to_fsharp_literal, to_fsharp_string, to_fsharp_value, Representation,
+    TypeDefinition, TypeKind, UnionCase,
+};
+
+///Gives every union a `Converter` in its module that makes Newtonsoft.Json read and write it the
+///same way serde does. The converters need to be added to the `JsonSerializerSettings`, as an
+///attribute can't refer to them.
+///
+///Records and enums are left to Newtonsoft.Json itself, which needs a converter for `option` to
+///write `None` as `null`, like the one of FSharpLu.Json. Fields with a default get attributes that
+///make Newtonsoft.Json fill it in when the field is missing, and fields that are only optional
+///because they can be missing get left out when they are `None`, as serde doesn't accept `null`.
+pub(crate) fn add_converters(definition: &mut TypeDefinition) {
+    let type_name = definition.name.clone();
+    let converter = match (&mut definition.kind, &definition.representation) {
+        (TypeKind::Record(fields), _) => {
+            for field in fields {
+                if field.skip_if_none {
+                    field.attributes.push(
+                        "[<JsonProperty(NullValueHandling = NullValueHandling.Ignore)>]".to_owned(),
+                    );
+                }
+                if let Some(default) = &field.default {
+                    field.attributes.extend([
+                        format!(
+                            "[<System.ComponentModel.DefaultValue({})>]",
+                            to_fsharp_value(default, &field.type_name)
+                                .unwrap_or_else(|| to_fsharp_literal(default))
+                        ),
+                        "[<JsonProperty(DefaultValueHandling = DefaultValueHandling.Populate)>]"
+                            .to_owned(),
+                    ]);
+                }
+            }
+            None
+        }
+        (TypeKind::Union(cases), Representation::ExternallyTagged) => {
+            Some(gen_externally_tagged_converter(cases, &type_name))
+        }
+        (TypeKind::Union(cases), Representation::InternallyTagged { tag }) => {
+            Some(gen_internally_tagged_converter(cases, tag, &type_name))
+        }
+        (TypeKind::Union(cases), Representation::Untagged) => {
+            Some(gen_untagged_converter(cases, &type_name))
+        }
+        (TypeKind::Union(cases), Representation::Literals(values)) => {
+            Some(gen_literal_converter(cases, values, &type_name))
+        }
+        (TypeKind::Wrapper { of, .. }, _) => Some(gen_wrapper_converter(of, &type_name)),
+        _ => None,
+    };
+    definition.functions.extend(converter);
+}
+
+fn gen_converter(type_name: &str, read: &str, write: &str) -> String {
+    format!(
+        "type Converter() =
+    inherit JsonConverter<{type_name}>()
+{read}
+{write}"
+    )
+}
+
+fn gen_error(type_name: &str) -> String {
+    format!(
+        "raise (JsonSerializationException(sprintf \"Could not read %O as {}\" token))",
+        type_name
+    )
+}
+
+fn gen_externally_tagged_converter(cases: &[UnionCase], type_name: &str) -> String {
+    let readers = cases
+        .iter()
+        .map(|case| match &case.of {
+            None => format!(
+                "        | {}, None -> {}",
+                to_fsharp_string(&case.wire_name),
+                case.name
+            ),
+            Some(of) => match tuple_items(of) {
+                Some(items) => format!(
+                    "        | {}, Some (:? JArray as value) when value.Count = {} -> {}",
+                    to_fsharp_string(&case.wire_name),
+                    items.len(),
+                    read_tuple(&case.name, &items, "value")
+                ),
+                None => format!(
+                    "        | {}, Some value -> {} (value.ToObject<{}>(serializer))",
+                    to_fsharp_string(&case.wire_name),
+                    case.name,
+                    of
+                ),
+            },
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let writers = cases
+        .iter()
+        .map(|case| match &case.of {
+            None => format!(
+                "        | {} -> writer.WriteValue {}",
+                case.name,
+                to_fsharp_string(&case.wire_name)
+            ),
+            Some(of) => {
+                let (pattern, value) = write_value(&case.name, of);
+                format!(
+                    "        | {} -> JObject(JProperty({}, {})).WriteTo writer",
+                    pattern,
+                    to_fsharp_string(&case.wire_name),
+                    value
+                )
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    gen_converter(
+        type_name,
+        &format!(
+            "    override _.ReadJson(reader, _, _, _, serializer) =
+        let token = JToken.ReadFrom reader
+        let name, value =
+            match token with
+            | :? JObject as token when token.Count = 1 ->
+                let property = token.Properties() |> Seq.head
+                property.Name, Some property.Value
+            | _ when token.Type = JTokenType.String -> token.ToObject<string>(), None
+            | _ -> \"\", None
+        match name, value with
+{readers}
+        | _ -> {}",
+            gen_error(type_name)
+        ),
+        &format!(
+            "    override _.WriteJson(writer, value, serializer) =
+        match value with
+{writers}"
+        ),
+    )
+}
+
+fn gen_internally_tagged_converter(cases: &[UnionCase], tag: &str, type_name: &str) -> String {
+    let tag = to_fsharp_string(tag);
+    let readers = cases
+        .iter()
+        .map(|case| match &case.of {
+            None => format!(
+                "        | {} -> {}",
+                to_fsharp_string(&case.wire_name),
+                case.name
+            ),
+            Some(of) => format!(
+                "        | {} -> {} (token.ToObject<{}>(serializer))",
+                to_fsharp_string(&case.wire_name),
+                case.name,
+                of
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let writers = cases
+        .iter()
+        .map(|case| match &case.of {
+            None => format!(
+                "        | {} -> JObject(JProperty({}, {})).WriteTo writer",
+                case.name,
+                tag,
+                to_fsharp_string(&case.wire_name)
+            ),
+            Some(_) => format!(
+                "        | {} value -> JObject(JProperty({}, {}), JObject.FromObject(value, serializer).Properties()).WriteTo writer",
+                case.name,
+                tag,
+                to_fsharp_string(&case.wire_name)
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    gen_converter(
+        type_name,
+        &format!(
+            "    override _.ReadJson(reader, _, _, _, serializer) =
+        let token = JObject.Load reader
+        match token.Value<string>({tag}) with
+{readers}
+        | _ -> {}",
+            gen_error(type_name)
+        ),
+        &format!(
+            "    override _.WriteJson(writer, value, serializer) =
+        match value with
+{writers}"
+        ),
+    )
+}
+
+///generates a converter that reads the union by trying every case in order, and writes it without
+///saying which case it was.
+fn gen_untagged_converter(cases: &[UnionCase], type_name: &str) -> String {
+    let attempts = cases
+        .iter()
+        .filter_map(|case| {
+            case.of.as_ref().map(|of| match tuple_items(of) {
+                Some(items) => format!(
+                    "                (fun () -> match token with :? JArray as token when token.Count = {} -> {} | _ -> failwith \"not a tuple\")",
+                    items.len(),
+                    read_tuple(&case.name, &items, "token")
+                ),
+                None => format!(
+                    "                (fun () -> {} (token.ToObject<{}>(serializer)))",
+                    case.name, of
+                ),
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let writers = cases
+        .iter()
+        .map(|case| match &case.of {
+            Some(of) if tuple_items(of).is_some() => {
+                let (pattern, value) = write_value(&case.name, of);
+                format!("        | {} -> {}.WriteTo writer", pattern, value)
+            }
+            Some(_) => format!(
+                "        | {} value -> serializer.Serialize(writer, value)",
+                case.name
+            ),
+            None => format!("        | {} -> writer.WriteNull()", case.name),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    gen_converter(
+        type_name,
+        &format!(
+            "    override _.ReadJson(reader, _, _, _, serializer) =
+        let token = JToken.ReadFrom reader
+        let attempts =
+            [
+{attempts}
+            ]
+        attempts
+        |> List.tryPick (fun attempt -> try Some (attempt ()) with _ -> None)
+        |> Option.defaultWith (fun () -> {})",
+            gen_error(type_name)
+        ),
+        &format!(
+            "    override _.WriteJson(writer, value, serializer) =
+        match value with
+{writers}"
+        ),
+    )
+}
+
+///the types of the items of a case holding a tuple, which serde writes as an array.
+fn tuple_items(of: &str) -> Option<Vec<&str>> {
+    let items = split_top_level(of, " * ");
+    (items.len() > 1).then_some(items)
+}
+
+///reads the items of a tuple case from the `JArray` named `array` one by one, as Newtonsoft.Json
+///can't read a tuple from an array on its own.
+fn read_tuple(case_name: &str, items: &[&str], array: &str) -> String {
+    let items = items
+        .iter()
+        .enumerate()
+        .map(|(i, of)| format!("{}.[{}].ToObject<{}>(serializer)", array, i, of))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{} ({})", case_name, items)
+}
+
+///the pattern matching a case holding a value of the type `of`, together with the `JToken` that
+///is written for it. The items of a tuple are written as an array, like serde does.
+fn write_value(case_name: &str, of: &str) -> (String, String) {
+    match tuple_items(of) {
+        Some(items) => {
+            let names = (1..=items.len())
+                .map(|v| format!("value{}", v))
+                .collect::<Vec<_>>();
+            let tokens = names
+                .iter()
+                .map(|v| format!("JToken.FromObject({}, serializer)", v))
+                .collect::<Vec<_>>()
+                .join(", ");
+            (
+                format!("{} ({})", case_name, names.join(", ")),
+                format!("JArray({})", tokens),
+            )
+        }
+        None => (
+            format!("{} value", case_name),
+            "JToken.FromObject(value, serializer)".to_owned(),
+        ),
+    }
+}
+
+///generates a converter that reads the wrapped value and only wraps it when `create` accepts it.
+fn gen_wrapper_converter(of: &str, type_name: &str) -> String {
+    gen_converter(
+        type_name,
+        &format!(
+            "    override _.ReadJson(reader, _, _, _, serializer) =
+        let token = JToken.ReadFrom reader
+        match create (token.ToObject<{of}>(serializer)) with
+        | Ok value -> value
+        | Error errors ->
+            raise (JsonSerializationException(sprintf \"Could not read %O as {type_name}: %s\" token (String.concat \", \" errors)))"
+        ),
+        &format!(
+            "    override _.WriteJson(writer, value, serializer) =
+        match value with
+        | {type_name} value -> serializer.Serialize(writer, value)"
+        ),
+    )
+}
+
+///generates a converter that maps every case to the json value it stands for.
+fn gen_literal_converter(cases: &[UnionCase], values: &[Value], type_name: &str) -> String {
+    let cases = cases
+        .iter()
+        .zip(values)
+        .map(|(case, value)| {
+            format!(
+                "            JToken.Parse {}, {}",
+                to_fsharp_string(&value.to_string()),
+                case.name
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    gen_converter(
+        type_name,
+        &format!(
+            "    static let cases =
+        [
+{cases}
+        ]
+    override _.ReadJson(reader, _, _, _, _) =
+        let token = JToken.ReadFrom reader
+        cases
+        |> List.tryFind (fun (value, _) -> JToken.DeepEquals(value, token))
+        |> Option.map snd
+        |> Option.defaultWith (fun () -> {})",
+            gen_error(type_name)
+        ),
+        "    override _.WriteJson(writer, value, _) =
+        (cases |> List.find (fun (_, case) -> case = value) |> fst).WriteTo writer",
+    )
+}
@@ -0,0 +1,174 @@
+use serde_json::Value;
+
+use crate::{to_fsharp_string, Representation, TypeDefinition, TypeKind, UnionCase};
+
+///Makes System.Text.Json read and write the type the same way serde does, using the converters of
+///FSharp.SystemTextJson.
+///
+///Records and tagged unions get a `[<JsonFSharpConverter>]` attribute set up for the way serde
+///represents them. Untagged unions, unions of literal values and wrapper types get a `Converter`
+///in their module instead, which has to be added to the `JsonSerializerOptions` as an attribute
+///can't refer to it.
+///
+///The extra fields of records with flattened maps become a `[<JsonExtensionData>]` dictionary of
+///`JsonElement`, as that is the only type System.Text.Json can put them in.
+pub(crate) fn add_converters(definition: &mut TypeDefinition) {
+    let attribute = match &definition.representation {
+        Representation::Object { .. } => Some("[<JsonFSharpConverter>]".to_owned()),
+        Representation::ExternallyTagged => Some(format!(
+            "[<JsonFSharpConverter(BaseUnionEncoding = {})>]",
+            "JsonUnionEncoding.ExternalTag ||| JsonUnionEncoding.UnwrapFieldlessTags ||| JsonUnionEncoding.UnwrapSingleFieldCases"
+        )),
+        Representation::InternallyTagged { tag } => Some(format!(
+            "[<JsonFSharpConverter(BaseUnionEncoding = {}, UnionTagName = {})>]",
+            "JsonUnionEncoding.InternalTag ||| JsonUnionEncoding.UnwrapRecordCases",
+            to_fsharp_string(tag)
+        )),
+        Representation::Untagged | Representation::Literals(_) => None,
+    };
+    let has_attribute = attribute.is_some();
+    definition.attributes.extend(attribute);
+    let name = definition.name.clone();
+    let converter = match (&mut definition.kind, &definition.representation) {
+        (TypeKind::Record(fields), Representation::Object { extra_fields: true }) => {
+            if let Some(extra) = fields.last_mut() {
+                extra.type_name = EXTENSION_DATA_TYPE.to_owned();
+                extra.attributes.push("[<JsonExtensionData>]".to_owned());
+            }
+            None
+        }
+        (TypeKind::Union(cases), _) if has_attribute => {
+            for case in cases.iter_mut().filter(|v| v.name != v.wire_name) {
+                case.attributes.push(format!(
+                    "[<JsonName({})>]",
+                    to_fsharp_string(&case.wire_name)
+                ));
+            }
+            None
+        }
+        (TypeKind::Union(cases), Representation::Untagged) => {
+            Some(gen_untagged_converter(cases, &name))
+        }
+        (TypeKind::Union(cases), Representation::Literals(values)) => {
+            Some(gen_literal_converter(cases, values, &name))
+        }
+        (TypeKind::Wrapper { of, .. }, _) => Some(gen_wrapper_converter(of, &name)),
+        //System.Text.Json writes enums as their number already
+        _ => None,
+    };
+    definition.functions.extend(converter);
+}
+
+///the type of the field that System.Text.Json puts the properties in that are not part of the record
+const EXTENSION_DATA_TYPE: &str = "System.Collections.Generic.Dictionary<string, JsonElement>";
+
+///generates a converter that reads the union by trying every case in order, and writes it without
+///saying which case it was.
+fn gen_untagged_converter(cases: &[UnionCase], type_name: &str) -> String {
+    let attempts = cases
+        .iter()
+        .map(|case| match &case.of {
+            Some(of) => format!(
+                "                (fun () -> {} (JsonSerializer.Deserialize<{}>(json, options)))",
+                case.name, of
+            ),
+            None => format!(
+                "                (fun () -> if json = \"null\" then {} else failwith \"not null\")",
+                case.name
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let writers = cases
+        .iter()
+        .map(|case| match &case.of {
+            Some(_) => format!(
+                "        | {} value -> JsonSerializer.Serialize(writer, value, options)",
+                case.name
+            ),
+            None => format!("        | {} -> writer.WriteNullValue()", case.name),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "type Converter() =
+    inherit JsonConverter<{type_name}>()
+    override _.Read(reader, _, options) =
+        use document = JsonDocument.ParseValue(&reader)
+        let json = document.RootElement.GetRawText()
+        let attempts =
+            [
+{attempts}
+            ]
+        attempts
+        |> List.tryPick (fun attempt -> try Some (attempt ()) with _ -> None)
+        |> Option.defaultWith (fun () -> raise (JsonException(sprintf \"Could not read %s as {type_name}\" json)))
+    override _.Write(writer, value, options) =
+        match value with
+{writers}"
+    )
+}
+
+///generates a converter that reads the wrapped value and only wraps it when `create` accepts it.
+fn gen_wrapper_converter(of: &str, type_name: &str) -> String {
+    format!(
+        "type Converter() =
+    inherit JsonConverter<{type_name}>()
+    override _.Read(reader, _, options) =
+        match create (JsonSerializer.Deserialize<{of}>(&reader, options)) with
+        | Ok value -> value
+        | Error errors -> raise (JsonException(sprintf \"Could not read {type_name}: %s\" (String.concat \", \" errors)))
+    override _.Write(writer, value, options) =
+        match value with
+        | {type_name} value -> JsonSerializer.Serialize(writer, value, options)"
+    )
+}
+
+///generates a converter that maps every case to the json value it stands for.
+///The values are compared after decoding them, as the json can be written in more than one way,
+///like with escaped characters in strings.
+fn gen_literal_converter(cases: &[UnionCase], values: &[Value], type_name: &str) -> String {
+    let cases = cases
+        .iter()
+        .zip(values)
+        .map(|(case, value)| {
+            format!(
+                "            {}, {}",
+                to_fsharp_string(&value.to_string()),
+                case.name
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "type Converter() =
+    inherit JsonConverter<{type_name}>()
+    static let cases =
+        [
+{cases}
+        ]
+    static let rec equals (a : JsonElement) (b : JsonElement) =
+        match a.ValueKind, b.ValueKind with
+        | JsonValueKind.String, JsonValueKind.String -> a.GetString() = b.GetString()
+        | JsonValueKind.Number, JsonValueKind.Number -> a.GetDouble() = b.GetDouble()
+        | JsonValueKind.Array, JsonValueKind.Array ->
+            a.GetArrayLength() = b.GetArrayLength()
+            && Seq.forall2 equals (a.EnumerateArray()) (b.EnumerateArray())
+        | JsonValueKind.Object, JsonValueKind.Object ->
+            let properties = b.EnumerateObject() |> Seq.map (fun v -> v.Name, v.Value) |> Map.ofSeq
+            Seq.length (a.EnumerateObject()) = properties.Count
+            && a.EnumerateObject()
+               |> Seq.forall (fun v -> properties.ContainsKey v.Name && equals v.Value properties.[v.Name])
+        | a, b -> a = b
+    override _.Read(reader, _, _) =
+        use document = JsonDocument.ParseValue(&reader)
+        cases
+        |> List.tryFind (fun (value, _) ->
+            use expected = JsonDocument.Parse(value : string)
+            equals expected.RootElement document.RootElement)
+        |> Option.map snd
+        |> Option.defaultWith (fun () -> raise (JsonException(sprintf \"Could not read %s as {type_name}\" (document.RootElement.GetRawText()))))
+    override _.Write(writer, value, _) =
+        cases |> List.find (fun (_, case) -> case = value) |> fst |> writer.WriteRawValue"
+    )
+}
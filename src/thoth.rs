@@ -0,0 +1,644 @@
+use std::collections::HashSet;
+
+use serde_json::Value;
+
+use crate::{
+    indent, split_top_level, to_fsharp_string, Field, Representation, TypeDefinition, TypeKind,
+    UnionCase, ANY_JSON_VALUE,
+};
+
+///Generates the Thoth.Json `decoder` and `encode` functions of a type, reading and writing the
+///same json as serde does. Records also get `encodeFields`, which tagged unions use to put the
+///tag next to the fields.
+///
+///Types used by the definition are expected to have these functions as well, except for types
+///with a `.` in their name, which fall back to the automatic coders of Thoth. The decoders of
+///the types in `used_in_turn`, which use the definition as well, are only used once decoding,
+///as they may not be done yet when the decoder of the definition is built.
+pub(crate) fn gen_codecs(
+    definition: &TypeDefinition,
+    used_in_turn: &HashSet<String>,
+) -> Vec<String> {
+    let mut builder = CodecBuilder {
+        definition,
+        used_in_turn,
+        recursive: false,
+    };
+    match (&definition.kind, &definition.representation) {
+        (TypeKind::Record(fields), Representation::Object { extra_fields }) => {
+            builder.gen_record(fields, *extra_fields)
+        }
+        (TypeKind::Union(cases), Representation::ExternallyTagged) => {
+            builder.gen_externally_tagged(cases)
+        }
+        (TypeKind::Union(cases), Representation::InternallyTagged { tag }) => {
+            builder.gen_internally_tagged(cases, tag)
+        }
+        (TypeKind::Union(cases), Representation::Untagged) => builder.gen_untagged(cases),
+        (TypeKind::Union(cases), Representation::Literals(values)) => {
+            builder.gen_literal_union(cases, values)
+        }
+        (TypeKind::Enum(cases), _) => builder.gen_enum(cases),
+        (TypeKind::Wrapper { of, .. }, _) => builder.gen_wrapper(of),
+        _ => Vec::new(),
+    }
+}
+
+///the decoder and encoder of a type, as F# expressions
+struct Codec {
+    decoder: String,
+    encoder: String,
+}
+
+struct CodecBuilder<'a> {
+    definition: &'a TypeDefinition,
+    ///the types that use the definition as well, whose decoders have to be used lazily
+    used_in_turn: &'a HashSet<String>,
+    ///whether the definition refers to itself, in which case its functions need to be recursive
+    recursive: bool,
+}
+
+impl<'a> CodecBuilder<'a> {
+    fn codec(&mut self, type_name: &str) -> Codec {
+        let type_name = type_name.trim();
+        let parts = split_top_level(type_name, " * ");
+        if parts.len() > 1 {
+            let codecs = parts.iter().map(|v| self.codec(v)).collect::<Vec<_>>();
+            return Codec {
+                decoder: format!(
+                    "(Decode.tuple{} {})",
+                    codecs.len(),
+                    join_with(&codecs, |v| &v.decoder)
+                ),
+                encoder: format!(
+                    "(Encode.tuple{} {})",
+                    codecs.len(),
+                    join_with(&codecs, |v| &v.encoder)
+                ),
+            };
+        }
+        if let Some(inner) = type_name.strip_suffix("[]") {
+            let inner = self.codec(inner);
+            return Codec {
+                decoder: format!("(Decode.array {})", inner.decoder),
+                encoder: format!("(Array.map {} >> Encode.array)", inner.encoder),
+            };
+        }
+        if let Some((base, arguments)) = split_generic(type_name) {
+            let arguments = split_top_level(arguments, ",")
+                .into_iter()
+                .map(|v| self.codec(v))
+                .collect::<Vec<_>>();
+            return match (base, arguments.as_slice()) {
+                ("option", [inner]) => Codec {
+                    decoder: format!("(Decode.option {})", inner.decoder),
+                    encoder: format!("(Encode.option {})", inner.encoder),
+                },
+                ("voption", [inner]) => Codec {
+                    decoder: format!(
+                        "(Decode.option {} |> Decode.map ValueOption.ofOption)",
+                        inner.decoder
+                    ),
+                    encoder: format!("(ValueOption.toOption >> Encode.option {})", inner.encoder),
+                },
+                ("Map", [_, values]) => Codec {
+                    decoder: format!("(Decode.dict {})", values.decoder),
+                    encoder: format!("(Map.map (fun _ -> {}) >> Encode.dict)", values.encoder),
+                },
+                (base, arguments) if base == self.definition.name => self.self_codec(arguments),
+                (base, arguments) if self.used_in_turn.contains(base) => Codec {
+                    decoder: format!(
+                        "(fun path value -> {}.decoder {} path value)",
+                        base,
+                        join_with(arguments, |v| &v.decoder)
+                    ),
+                    encoder: format!("({}.encode {})", base, join_with(arguments, |v| &v.encoder)),
+                },
+                (base, arguments) => Codec {
+                    decoder: format!(
+                        "({}.decoder {})",
+                        base,
+                        join_with(arguments, |v| &v.decoder)
+                    ),
+                    encoder: format!("({}.encode {})", base, join_with(arguments, |v| &v.encoder)),
+                },
+            };
+        }
+        match type_name {
+            "string" | "int" | "float" | "bool" => Codec {
+                decoder: format!("Decode.{}", type_name),
+                encoder: format!("Encode.{}", type_name),
+            },
+            "System.Guid" => Codec {
+                decoder: "Decode.guid".to_owned(),
+                encoder: "Encode.guid".to_owned(),
+            },
+            "System.ValueTuple" => Codec {
+                decoder: "(Decode.nil (System.ValueTuple()))".to_owned(),
+                encoder: "(fun _ -> Encode.nil)".to_owned(),
+            },
+            ANY_JSON_VALUE => Codec {
+                decoder: "Decode.value".to_owned(),
+                encoder: "id".to_owned(),
+            },
+            x if x.starts_with('\'') => Codec {
+                decoder: format!("decode{}", &x[1..]),
+                encoder: format!("encode{}", &x[1..]),
+            },
+            x if x == self.definition.name => self.self_codec(&[]),
+            x if x.contains('.') || x == "object" => Codec {
+                decoder: format!("(Decode.Auto.generateDecoder<{}>())", x),
+                encoder: format!("(Encode.Auto.generateEncoder<{}>())", x),
+            },
+            x if self.used_in_turn.contains(x) => Codec {
+                decoder: format!("(fun path value -> {}.decoder path value)", x),
+                encoder: format!("{}.encode", x),
+            },
+            x => Codec {
+                decoder: format!("{}.decoder", x),
+                encoder: format!("{}.encode", x),
+            },
+        }
+    }
+    ///the codec for a reference of the definition to itself.
+    ///The decoder is wrapped in a function, as it isn't done being built yet when it is used.
+    fn self_codec(&mut self, arguments: &[Codec]) -> Codec {
+        self.recursive = true;
+        let decoders = arguments
+            .iter()
+            .map(|v| format!(" {}", v.decoder))
+            .collect::<String>();
+        let encoders = arguments
+            .iter()
+            .map(|v| format!(" {}", v.encoder))
+            .collect::<String>();
+        Codec {
+            decoder: format!("(fun path value -> decoder{} path value)", decoders),
+            encoder: match self.definition.kind {
+                TypeKind::Record(_) => format!("(encodeFields{} >> Encode.object)", encoders),
+                _ => format!("(encode{})", encoders),
+            },
+        }
+    }
+    fn rec_keyword(&self) -> &'static str {
+        if self.recursive {
+            "rec "
+        } else {
+            ""
+        }
+    }
+    ///the parameters that take the coders for the type parameters of a generic type
+    fn params(&self, kind: &str, coder: &str) -> String {
+        self.definition
+            .type_params
+            .iter()
+            .map(|v| format!(" ({}{} : {}<{}>)", kind, &v[1..], coder, v))
+            .collect()
+    }
+    fn decoder_function(&self, body: &str) -> String {
+        format!(
+            "let {}decoder{} : Decoder<{}> =\n{}",
+            self.rec_keyword(),
+            self.params("decode", "Decoder"),
+            self.definition.full_name(),
+            indent(body)
+        )
+    }
+    fn encode_function(&self, name: &str, return_type: &str, body: &str) -> String {
+        format!(
+            "let {}{}{} (value : {}) : {} =\n{}",
+            self.rec_keyword(),
+            name,
+            self.params("encode", "Encoder"),
+            self.definition.full_name(),
+            return_type,
+            indent(body)
+        )
+    }
+    fn gen_record(&mut self, fields: &[Field], extra_fields: bool) -> Vec<String> {
+        let (known, extra) = match (extra_fields, fields.split_last()) {
+            (true, Some((extra, known))) => (known, Some(extra)),
+            _ => (fields, None),
+        };
+        let codecs = known
+            .iter()
+            .map(|v| (v, self.codec(&v.type_name)))
+            .collect::<Vec<_>>();
+        let extra_codec = extra
+            .and_then(|v| split_generic(&v.type_name))
+            .and_then(|(_, arguments)| split_top_level(arguments, ",").pop())
+            .map(|v| self.codec(v));
+        let mut decoded_fields = codecs
+            .iter()
+            .map(|(field, codec)| {
+                let getter = match (split_generic(&field.type_name), &field.default) {
+                    (Some(("option", inner)), _) => {
+                        format!(
+                            "get.Optional.Field {} {}",
+                            wire(field),
+                            self.codec(inner).decoder
+                        )
+                    }
+                    (Some(("voption", inner)), _) => {
+                        format!(
+                            "get.Optional.Field {} {} |> ValueOption.ofOption",
+                            wire(field),
+                            self.codec(inner).decoder
+                        )
+                    }
+                    //the default is decoded like the field, as it is the json serde would use
+                    (_, Some(default)) => format!(
+                        "get.Optional.Field {name} {decoder} |> Option.defaultWith (fun () -> Decode.unsafeFromString {decoder} {default})",
+                        name = wire(field),
+                        decoder = codec.decoder,
+                        default = to_fsharp_string(&default.to_string())
+                    ),
+                    _ => format!("get.Required.Field {} {}", wire(field), codec.decoder),
+                };
+                format!("{} = {}", field.name, getter)
+            })
+            .collect::<Vec<_>>();
+        let mut functions = Vec::new();
+        if let (Some(extra), Some(extra_codec)) = (extra, &extra_codec) {
+            let known_names = known.iter().map(wire).collect::<Vec<_>>().join("; ");
+            functions.push(format!(
+                "let private extraFieldsDecoder{params} : Decoder<{type_name}> =
+    Decode.keyValuePairs Decode.value
+    |> Decode.andThen (fun fields ->
+        let decoded =
+            fields
+            |> List.filter (fun (key, _) -> not (List.contains key [ {known_names} ]))
+            |> List.map (fun (key, value) -> Decode.fromValue \"$\" {decoder} value |> Result.map (fun value -> key, value))
+        match decoded |> List.tryPick (function Error error -> Some error | Ok _ -> None) with
+        | Some error -> Decode.fail error
+        | None -> decoded |> List.choose Result.toOption |> Map.ofList |> Decode.succeed)",
+                params = self.params("decode", "Decoder"),
+                type_name = extra.type_name,
+                decoder = extra_codec.decoder,
+            ));
+            let arguments = self
+                .definition
+                .type_params
+                .iter()
+                .map(|v| format!(" decode{}", &v[1..]))
+                .collect::<String>();
+            let extra_decoder = if arguments.is_empty() {
+                "extraFieldsDecoder".to_owned()
+            } else {
+                format!("(extraFieldsDecoder{})", arguments)
+            };
+            decoded_fields.push(format!(
+                "{} = get.Required.Raw {}",
+                extra.name, extra_decoder
+            ));
+        }
+        let decoder = format!(
+            "Decode.object (fun get ->\n    {{ {} }})",
+            decoded_fields.join("\n      ")
+        );
+        let encoded_fields = codecs
+            .iter()
+            .filter(|(field, _)| !field.skip_if_none)
+            .map(|(field, codec)| {
+                format!("{}, {} value.{}", wire(field), codec.encoder, field.name)
+            })
+            .collect::<Vec<_>>();
+        let mut encoder = if encoded_fields.is_empty() {
+            "[]".to_owned()
+        } else {
+            format!("[ {} ]", encoded_fields.join("\n  "))
+        };
+        //serde can't read `null` for these, so they are left out when missing
+        for field in known.iter().filter(|v| v.skip_if_none) {
+            if let Some((option, inner)) = split_generic(&field.type_name) {
+                let module = if option == "voption" {
+                    "ValueOption"
+                } else {
+                    "Option"
+                };
+                encoder.push_str(&format!(
+                    "\n@ (value.{} |> {}.toList |> List.map (fun value -> {}, {} value))",
+                    field.name,
+                    module,
+                    wire(field),
+                    self.codec(inner).encoder
+                ));
+            }
+        }
+        if let (Some(extra), Some(extra_codec)) = (extra, &extra_codec) {
+            encoder.push_str(&format!(
+                "\n@ (value.{} |> Map.toList |> List.map (fun (key, value) -> key, {} value))",
+                extra.name, extra_codec.encoder
+            ));
+        }
+        let arguments = self
+            .definition
+            .type_params
+            .iter()
+            .map(|v| format!(" encode{}", &v[1..]))
+            .collect::<String>();
+        functions.push(self.decoder_function(&decoder));
+        functions.push(self.encode_function("encodeFields", "(string * JsonValue) list", &encoder));
+        //encode only calls encodeFields, so it never has to be recursive
+        self.recursive = false;
+        functions.push(self.encode_function(
+            "encode",
+            "JsonValue",
+            &format!("Encode.object (encodeFields{} value)", arguments),
+        ));
+        functions
+    }
+    fn gen_externally_tagged(&mut self, cases: &[UnionCase]) -> Vec<String> {
+        let codecs = cases
+            .iter()
+            .map(|v| (v, v.of.as_ref().map(|v| self.codec(v))))
+            .collect::<Vec<_>>();
+        let unit_cases = codecs
+            .iter()
+            .filter(|(_, codec)| codec.is_none())
+            .map(|(case, _)| (to_fsharp_string(&case.wire_name), case.name.to_owned()))
+            .collect::<Vec<_>>();
+        let mut alternatives = Vec::new();
+        if !unit_cases.is_empty() {
+            alternatives.push(self.match_decoder("Decode.string", &unit_cases));
+        }
+        alternatives.extend(codecs.iter().filter_map(|(case, codec)| {
+            codec.as_ref().map(|codec| {
+                format!(
+                    "Decode.field {} {} |> Decode.map {}",
+                    to_fsharp_string(&case.wire_name),
+                    codec.decoder,
+                    case.name
+                )
+            })
+        }));
+        let arms = codecs
+            .iter()
+            .map(|(case, codec)| match codec {
+                None => format!(
+                    "| {} -> Encode.string {}",
+                    case.name,
+                    to_fsharp_string(&case.wire_name)
+                ),
+                Some(codec) => {
+                    let (pattern, value) = case_pattern(case);
+                    format!(
+                        "| {} -> Encode.object [ {}, {} {} ]",
+                        pattern,
+                        to_fsharp_string(&case.wire_name),
+                        codec.encoder,
+                        value
+                    )
+                }
+            })
+            .collect::<Vec<_>>();
+        vec![
+            self.decoder_function(&one_of(alternatives)),
+            self.encode_function("encode", "JsonValue", &match_value(&arms)),
+        ]
+    }
+    fn gen_internally_tagged(&mut self, cases: &[UnionCase], tag: &str) -> Vec<String> {
+        let tag = to_fsharp_string(tag);
+        let mut decoder_arms = Vec::new();
+        let mut encoder_arms = Vec::new();
+        for case in cases {
+            let wire_name = to_fsharp_string(&case.wire_name);
+            match &case.of {
+                None => {
+                    decoder_arms.push(format!("| {} -> Decode.succeed {}", wire_name, case.name));
+                    encoder_arms.push(format!(
+                        "| {} -> Encode.object [ {}, Encode.string {} ]",
+                        case.name, tag, wire_name
+                    ));
+                }
+                Some(of) => {
+                    decoder_arms.push(format!(
+                        "| {} -> {} |> Decode.map {}",
+                        wire_name,
+                        self.codec(of).decoder,
+                        case.name
+                    ));
+                    encoder_arms.push(format!(
+                        "| {} value -> Encode.object (({}, Encode.string {}) :: {}.encodeFields value)",
+                        case.name, tag, wire_name, of
+                    ));
+                }
+            }
+        }
+        decoder_arms.push(self.fail_arm());
+        let decoder = format!(
+            "Decode.field {} Decode.string\n|> Decode.andThen (function\n    {})",
+            tag,
+            decoder_arms.join("\n    ")
+        );
+        vec![
+            self.decoder_function(&decoder),
+            self.encode_function("encode", "JsonValue", &match_value(&encoder_arms)),
+        ]
+    }
+    fn gen_untagged(&mut self, cases: &[UnionCase]) -> Vec<String> {
+        let mut alternatives = Vec::new();
+        let mut arms = Vec::new();
+        for case in cases {
+            match &case.of {
+                Some(of) => {
+                    let codec = self.codec(of);
+                    let (pattern, value) = case_pattern(case);
+                    alternatives.push(format!("{} |> Decode.map {}", codec.decoder, case.name));
+                    arms.push(format!("| {} -> {} {}", pattern, codec.encoder, value));
+                }
+                None => {
+                    alternatives.push(format!("Decode.nil {}", case.name));
+                    arms.push(format!("| {} -> Encode.nil", case.name));
+                }
+            }
+        }
+        vec![
+            self.decoder_function(&one_of(alternatives)),
+            self.encode_function("encode", "JsonValue", &match_value(&arms)),
+        ]
+    }
+    ///the wrapped value is decoded first, and only wrapped when `create` accepts it.
+    fn gen_wrapper(&mut self, of: &str) -> Vec<String> {
+        let codec = self.codec(of);
+        let name = &self.definition.name;
+        vec![
+            self.decoder_function(&format!(
+                "{}
+|> Decode.andThen (fun value ->
+    match create value with
+    | Ok value -> Decode.succeed value
+    | Error errors -> Decode.fail (String.concat \", \" errors))",
+                codec.decoder
+            )),
+            self.encode_function(
+                "encode",
+                "JsonValue",
+                &match_value(&[format!("| {} value -> {} value", name, codec.encoder)]),
+            ),
+        ]
+    }
+    fn gen_literal_union(&mut self, cases: &[UnionCase], values: &[Value]) -> Vec<String> {
+        let cases = cases.iter().zip(values).collect::<Vec<_>>();
+        let of_kind = |is_kind: fn(&Value) -> bool, pattern: fn(&Value) -> String| {
+            cases
+                .iter()
+                .filter(|(_, value)| is_kind(value))
+                .map(|(case, value)| (pattern(value), case.name.to_owned()))
+                .collect::<Vec<_>>()
+        };
+        let mut alternatives = cases
+            .iter()
+            .filter(|(_, value)| value.is_null())
+            .map(|(case, _)| format!("Decode.nil {}", case.name))
+            .collect::<Vec<_>>();
+        for (decoder, arms) in [
+            ("Decode.bool", of_kind(Value::is_boolean, Value::to_string)),
+            (
+                "Decode.float",
+                of_kind(Value::is_number, |v| {
+                    format!("value when value = {}", to_float_literal(v))
+                }),
+            ),
+            ("Decode.string", of_kind(Value::is_string, Value::to_string)),
+            (
+                "Decode.value",
+                of_kind(
+                    |v| v.is_array() || v.is_object(),
+                    |v| {
+                        format!(
+                            "value when Encode.toString 0 value = {}",
+                            to_fsharp_string(&v.to_string())
+                        )
+                    },
+                ),
+            ),
+        ] {
+            if !arms.is_empty() {
+                alternatives.push(self.match_decoder(decoder, &arms));
+            }
+        }
+        let arms = cases
+            .iter()
+            .map(|(case, value)| {
+                let encoded = match value {
+                    Value::Null => "Encode.nil".to_owned(),
+                    Value::Bool(x) => format!("Encode.bool {}", x),
+                    Value::Number(_) => format!("Encode.float {}", to_float_literal(value)),
+                    Value::String(x) => format!("Encode.string {}", to_fsharp_string(x)),
+                    Value::Array(_) | Value::Object(_) => format!(
+                        "Decode.unsafeFromString Decode.value {}",
+                        to_fsharp_string(&value.to_string())
+                    ),
+                };
+                format!("| {} -> {}", case.name, encoded)
+            })
+            .collect::<Vec<_>>();
+        vec![
+            self.decoder_function(&one_of(alternatives)),
+            self.encode_function("encode", "JsonValue", &match_value(&arms)),
+        ]
+    }
+    fn gen_enum(&mut self, cases: &[(String, String)]) -> Vec<String> {
+        let number_type = if cases.iter().any(|(_, value)| value.ends_with('L')) {
+            "int64"
+        } else {
+            "int"
+        };
+        let type_name = &self.definition.name;
+        let arms = cases
+            .iter()
+            .map(|(name, value)| (value.to_owned(), format!("{}.{}", type_name, name)))
+            .collect::<Vec<_>>();
+        vec![
+            self.decoder_function(&self.match_decoder(&format!("Decode.{}", number_type), &arms)),
+            self.encode_function(
+                "encode",
+                "JsonValue",
+                &format!("Encode.{0} ({0} value)", number_type),
+            ),
+        ]
+    }
+    ///a decoder that reads a value with `decoder` and picks the case belonging to the value
+    fn match_decoder(&self, decoder: &str, arms: &[(String, String)]) -> String {
+        let arms = arms
+            .iter()
+            .map(|(pattern, case)| format!("| {} -> Decode.succeed {}", pattern, case))
+            .chain(std::iter::once(self.fail_arm()))
+            .collect::<Vec<_>>()
+            .join("\n    ");
+        format!("{}\n|> Decode.andThen (function\n    {})", decoder, arms)
+    }
+    fn fail_arm(&self) -> String {
+        format!(
+            "| other -> Decode.fail (sprintf \"Could not read %A as {}\" other)",
+            self.definition.name
+        )
+    }
+}
+
+///the pattern matching a case that holds a value, together with the value it binds.
+///A case holding a tuple has a field for every item, so those are bound one by one.
+fn case_pattern(case: &UnionCase) -> (String, String) {
+    let items = case
+        .of
+        .as_deref()
+        .map(|v| split_top_level(v, " * ").len())
+        .unwrap_or(1);
+    if items == 1 {
+        return (format!("{} value", case.name), "value".to_owned());
+    }
+    let value = format!(
+        "({})",
+        (1..=items)
+            .map(|v| format!("value{}", v))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    (format!("{} {}", case.name, value), value)
+}
+
+fn wire(field: &Field) -> String {
+    to_fsharp_string(&field.name)
+}
+
+fn to_float_literal(a: &Value) -> String {
+    match a.as_f64() {
+        Some(_) if a.is_f64() => a.to_string(),
+        _ => format!("{}.0", a),
+    }
+}
+
+fn join_with(codecs: &[Codec], part: fn(&Codec) -> &String) -> String {
+    codecs
+        .iter()
+        .map(part)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn one_of(alternatives: Vec<String>) -> String {
+    match alternatives.as_slice() {
+        [single] => single.to_owned(),
+        _ => format!(
+            "Decode.oneOf [\n{}\n]",
+            alternatives
+                .iter()
+                .map(|v| indent(v))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ),
+    }
+}
+
+fn match_value(arms: &[String]) -> String {
+    format!("match value with\n{}", arms.join("\n"))
+}
+
+///splits a type like `option<int>` into `option` and `int`
+fn split_generic(type_name: &str) -> Option<(&str, &str)> {
+    let inner = type_name.strip_suffix('>')?;
+    let (base, arguments) = inner.split_once('<')?;
+    Some((base, arguments))
+}
@@ -0,0 +1,167 @@
+use serde_json::Number;
+
+use crate::{
+    indent, split_optional, to_fsharp_string, to_fsharp_value, Constraint, Field, TypeDefinition,
+    TypeKind,
+};
+
+///Generates `validate` for a record, which checks that its fields follow the constraints of the
+///schema and returns a message for every one that doesn't.
+///
+///Only the fields of the record itself are checked, not the fields of the types they hold.
+pub(crate) fn gen_validate(definition: &TypeDefinition) -> Option<String> {
+    let TypeKind::Record(fields) = &definition.kind else {
+        return None;
+    };
+    let checks = fields
+        .iter()
+        .filter_map(gen_field_checks)
+        .collect::<Vec<_>>();
+    Some(format!(
+        "let validate (value : {name}) : Result<{name}, string list> =\n{}",
+        indent(&collect_errors(&checks, "value")),
+        name = definition.full_name()
+    ))
+}
+
+///Generates the functions of a wrapper type. `create` checks the constraints before wrapping the
+///value, `value` unwraps it again. The messages about failed constraints are about `subject`.
+pub(crate) fn gen_wrapper_functions(
+    type_name: &str,
+    of: &str,
+    constraints: &[Constraint],
+    subject: &str,
+) -> Vec<String> {
+    let checks = gen_checks(subject, constraints, "value", of);
+    vec![
+        format!(
+            "let create (value : {of}) : Result<{type_name}, string list> =\n{}",
+            indent(&collect_errors(
+                checks.as_slice(),
+                &format!("({} value)", type_name)
+            ))
+        ),
+        format!("let value ({type_name} value) : {of} = value"),
+    ]
+}
+
+///runs the checks, resulting in `result` when none of them fails.
+fn collect_errors(checks: &[String], result: &str) -> String {
+    if checks.is_empty() {
+        return format!("Ok {}", result);
+    }
+    format!(
+        "[\n{}\n]\n|> function\n    | [] -> Ok {}\n    | errors -> Error errors",
+        indent(&checks.join("\n")),
+        result
+    )
+}
+
+///the checks of a field, which only happen when an optional field holds a value.
+fn gen_field_checks(field: &Field) -> Option<String> {
+    match split_optional(&field.type_name) {
+        Some((kind, inner)) => {
+            let case = if kind == "voption" {
+                "ValueSome"
+            } else {
+                "Some"
+            };
+            let checks = gen_checks(&field.name, &field.constraints, "v", inner);
+            (!checks.is_empty()).then(|| {
+                format!(
+                    "match value.{} with\n| {} v ->\n{}\n| _ -> ()",
+                    field.name,
+                    case,
+                    indent(&checks.join("\n"))
+                )
+            })
+        }
+        None => {
+            let checks = gen_checks(
+                &field.name,
+                &field.constraints,
+                &format!("value.{}", field.name),
+                &field.type_name,
+            );
+            (!checks.is_empty()).then(|| checks.join("\n"))
+        }
+    }
+}
+
+///the checks of the value `expression` of the type `type_name`, every one resulting in a message
+///about `name` when it fails. Values that can't be written as F# for the type are not checked.
+fn gen_checks(
+    name: &str,
+    constraints: &[Constraint],
+    expression: &str,
+    type_name: &str,
+) -> Vec<String> {
+    constraints
+        .iter()
+        .filter_map(|constraint| {
+            let (condition, message) = match constraint {
+                Constraint::Const(x) => (
+                    format!("{} <> {}", expression, to_fsharp_value(x, type_name)?),
+                    format!("must be {}", x),
+                ),
+                Constraint::MinLength(x) => (
+                    format!("{}.Length < {}", expression, x),
+                    format!("must be at least {} characters long", x),
+                ),
+                Constraint::MaxLength(x) => (
+                    format!("{}.Length > {}", expression, x),
+                    format!("must be at most {} characters long", x),
+                ),
+                Constraint::Pattern(x) => (
+                    format!(
+                        "not (System.Text.RegularExpressions.Regex.IsMatch({}, {}))",
+                        expression,
+                        to_fsharp_string(x)
+                    ),
+                    format!("must match {}", x),
+                ),
+                Constraint::Minimum(x) => (
+                    compare(expression, type_name, "<", x),
+                    format!("must be at least {}", x),
+                ),
+                Constraint::Maximum(x) => (
+                    compare(expression, type_name, ">", x),
+                    format!("must be at most {}", x),
+                ),
+                Constraint::ExclusiveMinimum(x) => (
+                    compare(expression, type_name, "<=", x),
+                    format!("must be more than {}", x),
+                ),
+                Constraint::ExclusiveMaximum(x) => (
+                    compare(expression, type_name, ">=", x),
+                    format!("must be less than {}", x),
+                ),
+                Constraint::MinItems(x) => (
+                    format!("{}.Length < {}", expression, x),
+                    format!("must have at least {} items", x),
+                ),
+                Constraint::MaxItems(x) => (
+                    format!("{}.Length > {}", expression, x),
+                    format!("must have at most {} items", x),
+                ),
+            };
+            Some(format!(
+                "if {} then {}",
+                condition,
+                to_fsharp_string(&format!("{} {}", name, message))
+            ))
+        })
+        .collect()
+}
+
+///compares a number to a bound of the schema, which is a float even when the number is an int.
+fn compare(expression: &str, type_name: &str, operator: &str, bound: &Number) -> String {
+    let bound = bound.as_f64().unwrap_or_default();
+    if type_name == "int" && bound.fract() == 0.0 {
+        format!("{} {} {}", expression, operator, bound as i64)
+    } else if type_name == "float" {
+        format!("{} {} {:?}", expression, operator, bound)
+    } else {
+        format!("float {} {} {:?}", expression, operator, bound)
+    }
+}
@@ -0,0 +1,503 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use schemars::schema::RootSchema;
+use similar::TextDiff;
+
+use crate::{
+    gen, to_pascal_case, Config, ExternalTypeCollector, Result, Serializer, TypeDefinition,
+};
+
+///What every generated file starts with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileHeader {
+    ///`namespace X`. Every file uses the same namespace.
+    Namespace(String),
+    ///`module X`. When the types are split over multiple files, every file gets its own module
+    ///named after the group, like `X.Users`, which the files after it open.
+    Module(String),
+}
+
+///How the types get divided over the files.
+pub enum Grouping {
+    ///Everything ends up in a single file, with the given name (without `.fs`).
+    SingleFile(String),
+    ///Every Rust module gets its own file. Types that are only pulled in as dependency end up
+    ///in the file of the type that needed them first.
+    ByModulePath,
+    ///The function gets the name of a type and returns the name of the file it should be in.
+    Custom(Box<dyn Fn(&str) -> String>),
+}
+
+///A file as written by [FSharpWriter]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedFile {
+    ///the name of the file, including the `.fs` extension.
+    pub name: String,
+    pub contents: String,
+}
+
+///A file on disk that doesn't contain what would be generated for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleFile {
+    pub path: PathBuf,
+    ///a unified diff going from the file on disk to what would be generated.
+    ///A file that doesn't exist is treated as being empty.
+    pub diff: String,
+}
+
+struct WrittenType {
+    group: String,
+    definition: Rc<TypeDefinition>,
+}
+
+///The rendered text of the types written by a [FSharpWriter], which can be kept between writers
+///so only the types that changed get rendered again, like in watch mode.
+///
+///A type counts as unchanged while the [ExternalTypeCollector] hands out the same definition,
+///which it does until [ExternalTypeCollector::update_schema] finds that its schema changed.
+#[derive(Debug, Default)]
+pub struct RenderCache {
+    ///the recursive groups of types and their text, by the name of the first type in the group
+    groups: HashMap<String, (Vec<Rc<TypeDefinition>>, String)>,
+    last_rendered: Vec<String>,
+}
+
+impl RenderCache {
+    ///The names of the types that had to be rendered during the last write, as they were not in
+    ///the cache yet.
+    pub fn last_rendered(&self) -> &[String] {
+        &self.last_rendered
+    }
+    fn render(&mut self, definitions: &[&Rc<TypeDefinition>]) -> String {
+        let key = &definitions[0].name;
+        if let Some((cached, text)) = self.groups.get(key) {
+            if cached.len() == definitions.len()
+                && cached
+                    .iter()
+                    .zip(definitions)
+                    .all(|(a, b)| Rc::ptr_eq(a, b))
+            {
+                return text.clone();
+            }
+        }
+        let mut text = String::new();
+        let borrowed = definitions.iter().map(|v| v.as_ref()).collect::<Vec<_>>();
+        TypeDefinition::write_group_to(&borrowed, &mut text)
+            .expect("writing to a string can't fail");
+        self.last_rendered
+            .extend(definitions.iter().map(|v| v.name.clone()));
+        self.groups.insert(
+            key.clone(),
+            (
+                definitions.iter().map(|v| Rc::clone(v)).collect(),
+                text.clone(),
+            ),
+        );
+        text
+    }
+}
+
+///Turns generated types into complete `.fs` files.
+///
+///F# needs types to be defined before they are used, including across files.
+///So, the files are returned in the order they need to be compiled in.
+pub struct FSharpWriter {
+    header: FileHeader,
+    serializer: Serializer,
+    ///the namespaces to open next to the ones the serializer needs
+    opens: Vec<String>,
+    grouping: Grouping,
+    ///the module paths of types that should not end up in the group of the type needing them
+    module_paths: HashMap<String, String>,
+    types: Vec<WrittenType>,
+}
+
+impl FSharpWriter {
+    pub fn new(header: FileHeader) -> Self {
+        Self {
+            header,
+            serializer: Serializer::default(),
+            opens: Vec::new(),
+            grouping: Grouping::SingleFile("Types".to_owned()),
+            module_paths: HashMap::new(),
+            types: Vec::new(),
+        }
+    }
+    ///Creates a writer that uses the output settings of the config.
+    pub fn from_config(config: &Config) -> Self {
+        let mut writer = Self::new(config.output.header());
+        writer.set_serializer(config.output.serializer);
+        for open in &config.output.opens {
+            writer.add_open(open);
+        }
+        writer.set_grouping(config.output.grouping());
+        writer
+    }
+    ///Opens the namespaces of `serializer` in every file. Defaults to FSharp.Json.
+    ///This should be the same serializer the collector uses.
+    pub fn set_serializer(&mut self, serializer: Serializer) {
+        self.serializer = serializer;
+    }
+    ///Adds a namespace to open at the top of every file, after the ones the serializer needs.
+    pub fn add_open(&mut self, namespace: &str) {
+        if !self.opens.iter().any(|v| v == namespace) {
+            self.opens.push(namespace.to_owned());
+        }
+    }
+    pub fn set_grouping(&mut self, grouping: Grouping) {
+        self.grouping = grouping;
+    }
+    ///Tells the writer which Rust module the type named `type_name` is from.
+    ///With [Grouping::ByModulePath] the type then ends up in the file of that module, even when it
+    ///is added as a dependency of a type from another module.
+    pub fn set_module_path(&mut self, type_name: &str, module_path: &str) {
+        self.module_paths
+            .insert(type_name.to_owned(), module_path.to_owned());
+    }
+    ///generates `A` and every type it needs.
+    pub fn add_type<A: schemars::JsonSchema>(
+        &mut self,
+        collector: &mut ExternalTypeCollector,
+    ) -> Result<()> {
+        self.add_schema(
+            schemars::schema_for!(A),
+            &get_module_path(std::any::type_name::<A>()),
+            collector,
+        )
+    }
+    ///generates the type described by the schema and every type it needs.
+    ///`module_path` is the Rust module the type is from, like `my_crate::api::users`.
+    pub fn add_schema(
+        &mut self,
+        schema: RootSchema,
+        module_path: &str,
+        collector: &mut ExternalTypeCollector,
+    ) -> Result<()> {
+        let key = <&TypeDefinition>::from(gen(schema, collector)?).full_name();
+        collector.get_new_types().for_each(drop);
+        let module_group = module_path_to_group(module_path);
+        //every type the schema needs is taken from the collector, instead of only the new ones.
+        //That way a collector can be used for multiple writers, like watch mode does.
+        for definition in collector.get_type_with_dependencies(&key) {
+            if self
+                .types
+                .iter()
+                .any(|v| v.definition.name == definition.name)
+            {
+                continue;
+            }
+            let group = match &self.grouping {
+                Grouping::SingleFile(file) => file.to_owned(),
+                Grouping::ByModulePath => self
+                    .module_paths
+                    .get(&definition.name)
+                    .map(|v| module_path_to_group(v))
+                    .unwrap_or_else(|| module_group.clone()),
+                Grouping::Custom(f) => f(&definition.name),
+            };
+            self.types.push(WrittenType { group, definition });
+        }
+        Ok(())
+    }
+    ///The files, in the order they need to be compiled in.
+    pub fn files(&self) -> Vec<GeneratedFile> {
+        self.files_with(&mut RenderCache::default())
+    }
+    ///like [FSharpWriter::files], taking the types that didn't change from the cache.
+    fn files_with(&self, cache: &mut RenderCache) -> Vec<GeneratedFile> {
+        cache.last_rendered.clear();
+        let groups = self.ordered_groups();
+        groups
+            .iter()
+            .enumerate()
+            .map(|(i, group)| {
+                let body = self
+                    .types_in(group)
+                    .into_iter()
+                    .map(|definitions| cache.render(&definitions))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                GeneratedFile {
+                    name: format!("{}.fs", group),
+                    contents: format!("{}{}\n", self.file_start(&groups, i), body),
+                }
+            })
+            .collect()
+    }
+    ///Writes the file that belongs to the `index`th group of `groups` to `out`, one recursive group
+    ///of types at a time.
+    fn write_file(&self, groups: &[String], index: usize, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(self.file_start(groups, index).as_bytes())?;
+        for (i, definitions) in self.types_in(&groups[index]).into_iter().enumerate() {
+            if i != 0 {
+                out.write_all(b"\n")?;
+            }
+            let definitions = definitions.iter().map(|v| v.as_ref()).collect::<Vec<_>>();
+            TypeDefinition::write_group_to_io(&definitions, out)?;
+        }
+        out.write_all(b"\n")
+    }
+    ///the header and the opens of the file that belongs to the `index`th group of `groups`
+    fn file_start(&self, groups: &[String], index: usize) -> String {
+        let module_of = |group: &String| match &self.header {
+            FileHeader::Module(x) if groups.len() > 1 => Some(format!("{}.{}", x, group)),
+            _ => None,
+        };
+        let header = match (&self.header, module_of(&groups[index])) {
+            (FileHeader::Namespace(x), _) => format!("namespace {}", x),
+            (FileHeader::Module(_), Some(module)) => format!("module {}", module),
+            (FileHeader::Module(x), None) => format!("module {}", x),
+        };
+        let serializer_opens = self.serializer.opens();
+        let opens = serializer_opens
+            .iter()
+            .map(|v| v.to_string())
+            .chain(
+                self.opens
+                    .iter()
+                    .filter(|v| !serializer_opens.contains(&v.as_str()))
+                    .cloned(),
+            )
+            .chain(groups[..index].iter().filter_map(module_of))
+            .map(|v| format!("open {}\n", v))
+            .collect::<String>();
+        //the generated code uses the deprecated types and fields itself, which shouldn't warn.
+        //That includes deprecated types from other files.
+        let deprecated = self
+            .types
+            .iter()
+            .filter(|v| v.definition.deprecated)
+            .map(|v| v.definition.name.as_str())
+            .collect::<HashSet<_>>();
+        let nowarn = if self
+            .types
+            .iter()
+            .filter(|v| v.group == groups[index])
+            .any(|v| {
+                v.definition.contains_deprecated()
+                    || v.definition.used_names().any(|v| deprecated.contains(v))
+            }) {
+            "#nowarn \"44\"\n"
+        } else {
+            ""
+        };
+        format!("{}\n\n{}{}\n", header, nowarn, opens)
+    }
+    ///the types in the group, ordered so that a type comes after the types it uses. Types that
+    ///use each other end up together, to be written as a recursive group.
+    fn types_in(&self, group: &str) -> Vec<Vec<&Rc<TypeDefinition>>> {
+        let types = self
+            .types
+            .iter()
+            .filter(|v| v.group == group)
+            .map(|v| &v.definition)
+            .collect::<Vec<_>>();
+        let uses = types
+            .iter()
+            .map(|v| {
+                v.used_names()
+                    .filter_map(|used| types.iter().position(|other| other.name == used))
+                    .collect::<HashSet<_>>()
+            })
+            .collect::<Vec<_>>();
+        //every type that can be reached from a type, including itself
+        let reachable = (0..types.len())
+            .map(|start| {
+                let mut reached = HashSet::from([start]);
+                let mut todo = vec![start];
+                while let Some(i) = todo.pop() {
+                    todo.extend(uses[i].iter().filter(|v| reached.insert(**v)));
+                }
+                reached
+            })
+            .collect::<Vec<_>>();
+        let mut recursive_groups: Vec<Vec<usize>> = Vec::new();
+        for i in 0..types.len() {
+            match recursive_groups
+                .iter_mut()
+                .find(|v| reachable[i].contains(&v[0]) && reachable[v[0]].contains(&i))
+            {
+                Some(recursive_group) => recursive_group.push(i),
+                None => recursive_groups.push(vec![i]),
+            }
+        }
+        let recursive_groups = recursive_groups.iter().collect::<Vec<_>>();
+        order_by_dependencies(&recursive_groups, |i, placed| {
+            recursive_groups[i]
+                .iter()
+                .flat_map(|v| &uses[*v])
+                .all(|used| {
+                    recursive_groups[i].contains(used) || placed.iter().any(|v| v.contains(used))
+                })
+        })
+        .into_iter()
+        .map(|v| v.iter().map(|i| types[*i]).collect())
+        .collect()
+    }
+    ///The `<Compile Include="..." />` lines for a `.fsproj`, in the order the files need to be
+    ///compiled in.
+    pub fn fsproj_items(&self) -> String {
+        self.file_names()
+            .iter()
+            .map(|v| format!("    <Compile Include=\"{}\" />\n", v))
+            .collect()
+    }
+    ///The names of the files, in the order they need to be compiled in.
+    pub fn file_names(&self) -> Vec<String> {
+        self.ordered_groups()
+            .iter()
+            .map(|v| format!("{}.fs", v))
+            .collect()
+    }
+    ///Writes every file to `dir` and returns their paths, in the order they need to be compiled in.
+    pub fn write_to_dir(&self, dir: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let groups = self.ordered_groups();
+        (0..groups.len())
+            .map(|i| {
+                let path = dir.join(format!("{}.fs", groups[i]));
+                let mut out = BufWriter::new(fs::File::create(&path)?);
+                self.write_file(&groups, i, &mut out)?;
+                out.flush()?;
+                Ok(path)
+            })
+            .collect()
+    }
+    ///Like [FSharpWriter::write_to_dir], but only writes the files whose contents changed.
+    ///Returns the paths of the files that were written.
+    ///
+    ///Types that are in `cache` already aren't rendered again, so a cache that is kept between
+    ///writers only renders the types that are new or changed.
+    pub fn write_changed_to_dir(
+        &self,
+        dir: impl AsRef<Path>,
+        cache: &mut RenderCache,
+    ) -> io::Result<Vec<PathBuf>> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let mut written = Vec::new();
+        for file in self.files_with(cache) {
+            let path = dir.join(&file.name);
+            match fs::read_to_string(&path) {
+                Ok(x) if x == file.contents => continue,
+                Ok(_) => (),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+                Err(e) => return Err(e),
+            }
+            fs::write(&path, file.contents)?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+    ///Compares the files in `dir` with what would be written to it, without writing anything.
+    ///Returns the files that differ, which is empty if everything is up to date.
+    pub fn check_dir(&self, dir: impl AsRef<Path>) -> io::Result<Vec<StaleFile>> {
+        let dir = dir.as_ref();
+        let mut stale = Vec::new();
+        for file in self.files() {
+            let path = dir.join(&file.name);
+            let on_disk = match fs::read_to_string(&path) {
+                Ok(x) => x,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+                Err(e) => return Err(e),
+            };
+            if on_disk != file.contents {
+                let name = path.display().to_string();
+                let diff = TextDiff::from_lines(&on_disk, &file.contents)
+                    .unified_diff()
+                    .header(&format!("a/{}", name), &format!("b/{}", name))
+                    .to_string();
+                stale.push(StaleFile { path, diff });
+            }
+        }
+        Ok(stale)
+    }
+    ///orders the groups so that a group comes after the groups with types it uses.
+    ///Groups that don't depend on each other stay in the order they were first seen.
+    fn ordered_groups(&self) -> Vec<String> {
+        let mut groups: Vec<&str> = Vec::new();
+        let mut group_of_type = HashMap::new();
+        for written in &self.types {
+            if !groups.contains(&written.group.as_str()) {
+                groups.push(&written.group);
+            }
+            group_of_type.insert(written.definition.name.as_str(), written.group.as_str());
+        }
+        let mut dependencies: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for written in &self.types {
+            let used = dependencies.entry(&written.group).or_default();
+            written
+                .definition
+                .used_names()
+                .filter_map(|v| group_of_type.get(v))
+                .filter(|v| **v != written.group)
+                .for_each(|v| {
+                    used.insert(v);
+                });
+        }
+        order_by_dependencies(&groups, |i, placed| {
+            dependencies
+                .get(groups[i])
+                .map(|used| used.iter().all(|x| placed.contains(x)))
+                .unwrap_or(true)
+        })
+        .into_iter()
+        .map(ToOwned::to_owned)
+        .collect()
+    }
+}
+
+///orders `items` so that every item comes after the ones it depends on, according to
+///`dependencies_placed`, which gets the index of an item and the items ordered so far.
+///Items that don't depend on each other stay in the same order.
+fn order_by_dependencies<T: Copy>(
+    items: &[T],
+    dependencies_placed: impl Fn(usize, &[T]) -> bool,
+) -> Vec<T> {
+    let mut placed = vec![false; items.len()];
+    let mut ordered = Vec::with_capacity(items.len());
+    while ordered.len() < items.len() {
+        let next = (0..items.len())
+            .filter(|i| !placed[*i])
+            .find(|i| dependencies_placed(*i, &ordered))
+            //the items depend on each other, like groups in different files using each other.
+            //This can't be written in an order F# accepts, so the order they were found in is kept.
+            .or_else(|| (0..items.len()).find(|i| !placed[*i]))
+            .expect("there is always an item left while not everything is ordered");
+        placed[next] = true;
+        ordered.push(items[next]);
+    }
+    ordered
+}
+
+///`my_crate::api::users::User<my_crate::Other>` becomes `my_crate::api::users`
+pub(crate) fn get_module_path(type_name: &str) -> String {
+    let without_generics = type_name.split('<').next().unwrap_or(type_name);
+    without_generics
+        .rsplit_once("::")
+        .map(|v| v.0)
+        .unwrap_or(without_generics)
+        .to_owned()
+}
+
+///`my_crate::api::users` becomes `Api.Users`, while a type directly in the crate root ends up in
+///a group named after the crate.
+fn module_path_to_group(module_path: &str) -> String {
+    let parts = module_path.split("::").collect::<Vec<_>>();
+    let parts = match parts.as_slice() {
+        [_crate_name, rest @ ..] if !rest.is_empty() => rest,
+        all => all,
+    };
+    parts
+        .iter()
+        .map(|v| to_pascal_case(v))
+        .collect::<Vec<_>>()
+        .join(".")
+}
@@ -0,0 +1,223 @@
+use std::{
+    fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+const USER: &str = r##"{
+    "title": "User",
+    "type": "object",
+    "required": ["name", "address"],
+    "properties": {
+        "name": { "type": "string" },
+        "address": { "$ref": "#/definitions/Address" }
+    },
+    "definitions": {
+        "Address": { "type": "object", "required": ["street"], "properties": { "street": { "type": "string" } } }
+    }
+}"##;
+
+const ORDER: &str = r##"{
+    "title": "Order",
+    "type": "object",
+    "required": ["amount"],
+    "properties": {
+        "amount": { "type": "integer" }
+    }
+}"##;
+
+///creates a fresh directory with the schemas in it
+fn schema_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("type_gen_cli_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("schemas/nested")).unwrap();
+    fs::write(dir.join("schemas/user.json"), USER).unwrap();
+    fs::write(dir.join("schemas/nested/order.json"), ORDER).unwrap();
+    fs::write(dir.join("schemas/notes.txt"), "not a schema").unwrap();
+    dir
+}
+
+fn type_gen(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_type_gen"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn writes_to_stdout() {
+    let dir = schema_dir("stdout");
+    let output = type_gen(&["--module", "Api", dir.join("schemas").to_str().unwrap()]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("module Api\n\nopen FSharp.Json\n\ntype Address =\n"));
+    assert!(stdout.contains("type User =\n"));
+    assert!(stdout.contains("type Order =\n"));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn splits_files_per_schema() {
+    let dir = schema_dir("split");
+    let out = dir.join("out");
+    let output = type_gen(&[
+        "--split",
+        "--open",
+        "System",
+        "-o",
+        out.to_str().unwrap(),
+        dir.join("schemas/user.json").to_str().unwrap(),
+        dir.join("schemas/nested/order.json").to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    let user = fs::read_to_string(out.join("User.fs")).unwrap();
+    assert!(user.starts_with("namespace Types\n\nopen FSharp.Json\nopen System\n\n"));
+    assert!(user.contains("type Address =\n"));
+    assert!(fs::read_to_string(out.join("Order.fs"))
+        .unwrap()
+        .contains("type Order =\n"));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn serializer_can_be_picked() {
+    let dir = schema_dir("serializer");
+    let output = type_gen(&[
+        "--serializer",
+        "thoth",
+        dir.join("schemas/user.json").to_str().unwrap(),
+    ]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("namespace Types\n\nopen Thoth.Json\n\ntype Address =\n"));
+    assert!(stdout.contains("    let decoder : Decoder<User> =\n"));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn reports_bad_usage() {
+    let output = type_gen(&["--lang", "cobol", "schema.json"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Unsupported language `cobol`"));
+    let output = type_gen(&["--serializer", "yaml", "schema.json"]);
+    assert_eq!(output.status.code(), Some(2));
+    let output = type_gen(&["--optional-fields", "null", "schema.json"]);
+    assert_eq!(output.status.code(), Some(2));
+    let output = type_gen(&["does_not_exist.json"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn check_fails_on_outdated_files() {
+    let dir = schema_dir("check");
+    let out = dir.join("out");
+    let out = out.to_str().unwrap();
+    let schemas = dir.join("schemas");
+    let schemas = schemas.to_str().unwrap();
+    assert_eq!(
+        type_gen(&["--check", "-o", out, schemas]).status.code(),
+        Some(1)
+    );
+    assert!(type_gen(&["-o", out, schemas]).status.success());
+    assert!(type_gen(&["--check", "-o", out, schemas]).status.success());
+
+    fs::write(
+        dir.join("schemas/nested/order.json"),
+        ORDER.replace("integer", "string"),
+    )
+    .unwrap();
+    let output = type_gen(&["--check", "-o", out, schemas]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("-        amount : int\n+        amount : string\n"));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+///waits until `f` returns something, giving up after a while
+fn wait_for<T>(mut f: impl FnMut() -> Option<T>) -> T {
+    for _ in 0..500 {
+        if let Some(x) = f() {
+            return x;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+    panic!("gave up waiting")
+}
+
+#[test]
+fn watch_regenerates_changed_files() {
+    let dir = schema_dir("watch");
+    let out = dir.join("out");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_type_gen"))
+        .args([
+            "--watch",
+            "--split",
+            "--poll-interval",
+            "20",
+            "-o",
+            out.to_str().unwrap(),
+            dir.join("schemas").to_str().unwrap(),
+        ])
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    let user_path = out.join("User.fs");
+    let user = wait_for(|| fs::read_to_string(&user_path).ok());
+    let order = wait_for(|| fs::read_to_string(out.join("Order.fs")).ok());
+    let user_modified = fs::metadata(&user_path).unwrap().modified().unwrap();
+
+    fs::write(
+        dir.join("schemas/nested/order.json"),
+        ORDER.replace("integer", "string"),
+    )
+    .unwrap();
+    let new_order = wait_for(|| {
+        fs::read_to_string(out.join("Order.fs"))
+            .ok()
+            .filter(|v| v != &order)
+    });
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert!(new_order.contains("amount : string"));
+    //files without changes are left alone
+    assert_eq!(fs::read_to_string(&user_path).unwrap(), user);
+    assert_eq!(
+        fs::metadata(&user_path).unwrap().modified().unwrap(),
+        user_modified
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn options_overrule_the_config() {
+    let dir = schema_dir("config");
+    let config = dir.join("type_gen.toml");
+    fs::write(
+        &config,
+        "[output]\nmodule = \"FromConfig\"\nopens = [\"System\"]\n\n[type_overrides]\nAddress = \"Shared.Address\"\n",
+    )
+    .unwrap();
+    let schema = dir.join("schemas/user.json");
+    let output = type_gen(&["-c", config.to_str().unwrap(), schema.to_str().unwrap()]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.starts_with("module FromConfig\n\nopen FSharp.Json\nopen System\n\ntype User =\n")
+    );
+    assert!(stdout.contains("address : Shared.Address\n"));
+
+    let output = type_gen(&[
+        "-c",
+        config.to_str().unwrap(),
+        "--namespace",
+        "FromArgs",
+        schema.to_str().unwrap(),
+    ]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("namespace FromArgs\n"));
+
+    fs::write(&config, "[output]\nsplitt = true\n").unwrap();
+    let output = type_gen(&["-c", config.to_str().unwrap(), schema.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,31 @@
+#![allow(dead_code)]
+
+use schemars::JsonSchema;
+use type_gen::{gen, gen_from_type, ExternalTypeCollector, Serializer};
+
+///generates `A` for the serializer, after every type it needs.
+pub fn gen_all<A: JsonSchema>(serializer: Serializer) -> String {
+    gen_all_with(serializer, |x| String::from(gen_from_type::<A>(x).unwrap()))
+}
+
+///generates the type described by the json schema for the serializer, after every type it needs.
+pub fn gen_all_from_json(schema: &str, serializer: Serializer) -> String {
+    gen_all_with(serializer, |x| {
+        String::from(gen(serde_json::from_str(schema).unwrap(), x).unwrap())
+    })
+}
+
+fn gen_all_with(
+    serializer: Serializer,
+    generate: impl FnOnce(&mut ExternalTypeCollector) -> String,
+) -> String {
+    let mut collector = ExternalTypeCollector::new();
+    collector.set_serializer(serializer);
+    let generated_type = generate(&mut collector);
+    collector
+        .get_new_external_types()
+        .map(|v| v.1)
+        .chain(std::iter::once(generated_type))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
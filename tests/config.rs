@@ -0,0 +1,104 @@
+use type_gen::{
+    gen, Config, ConfigError, ExternalTypeCollector, FSharpWriter, FileHeader, NamingConvention,
+};
+
+const CONFIG: &str = r#"
+[output]
+module = "Api"
+opens = ["System"]
+
+[naming]
+convention = "underscore"
+hints = { "User.home_address" = "Home" }
+
+[type_overrides]
+Timestamp = "System.DateTime"
+
+[formats]
+uuid = "System.Guid"
+"#;
+
+const USER: &str = r##"{
+    "title": "User",
+    "type": "object",
+    "required": ["id", "email", "created", "address", "home_address"],
+    "properties": {
+        "id": { "type": "string", "format": "uuid" },
+        "parent": { "type": ["string", "null"], "format": "uuid" },
+        "email": { "type": "string", "format": "email" },
+        "created": { "$ref": "#/definitions/Timestamp" },
+        "address": { "type": "object", "properties": { "street": { "type": "string" } } },
+        "home_address": { "type": "object", "properties": { "city": { "type": "string" } } }
+    },
+    "definitions": {
+        "Timestamp": { "type": "string" }
+    }
+}"##;
+
+#[test]
+fn reads_toml_and_json() {
+    let config = Config::from_toml(CONFIG).unwrap();
+    assert_eq!(config.output.header(), FileHeader::Module("Api".to_owned()));
+    assert_eq!(config.naming.convention, NamingConvention::Underscore);
+    assert_eq!(config.formats["uuid"], "System.Guid");
+    let from_json = Config::from_json(
+        r#"{
+            "output": { "module": "Api", "opens": ["System"] },
+            "naming": { "convention": "underscore", "hints": { "User.home_address": "Home" } },
+            "type_overrides": { "Timestamp": "System.DateTime" },
+            "formats": { "uuid": "System.Guid" }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(config, from_json);
+    assert_eq!(Config::from_toml("").unwrap(), Config::default());
+}
+
+#[test]
+fn rejects_invalid_configs() {
+    assert!(matches!(
+        Config::from_toml("[output]\nnamespace = \"A\"\nmodule = \"B\""),
+        Err(ConfigError::Invalid(_))
+    ));
+    assert!(matches!(
+        Config::from_toml("[naming]\nhints = { address = \"A\" }"),
+        Err(ConfigError::Invalid(_))
+    ));
+    assert!(matches!(
+        Config::from_toml("[output]\nsplitt = true"),
+        Err(ConfigError::Toml(_))
+    ));
+}
+
+#[test]
+fn collector_uses_the_config() {
+    let config = Config::from_toml(CONFIG).unwrap();
+    let mut collector = ExternalTypeCollector::from_config(&config);
+    let generated = String::from(gen(serde_json::from_str(USER).unwrap(), &mut collector).unwrap());
+    assert!(generated.contains("        id : System.Guid\n"));
+    assert!(generated.contains("        parent : option<System.Guid>\n"));
+    assert!(generated.contains("        email : string\n"));
+    assert!(generated.contains("        created : System.DateTime\n"));
+    assert!(generated.contains("        address : User_Address\n"));
+    assert!(generated.contains("        home_address : Home\n"));
+    let dependencies = collector
+        .get_new_external_types()
+        .map(|v| v.0)
+        .collect::<Vec<_>>();
+    assert_eq!(dependencies, ["User_Address", "Home"]);
+}
+
+#[test]
+fn writer_uses_the_config() {
+    let config = Config::from_toml(CONFIG).unwrap();
+    let mut collector = ExternalTypeCollector::from_config(&config);
+    let mut writer = FSharpWriter::from_config(&config);
+    writer
+        .add_schema(serde_json::from_str(USER).unwrap(), "api", &mut collector)
+        .unwrap();
+    let files = writer.files();
+    assert_eq!(files[0].name, "Types.fs");
+    assert!(files[0]
+        .contents
+        .starts_with("module Api\n\nopen FSharp.Json\nopen System\n\n"));
+}
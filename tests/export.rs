@@ -0,0 +1,87 @@
+#![cfg(feature = "export")]
+
+use schemars::JsonSchema;
+use type_gen::{exported_types, gen_exported, ExternalTypeCollector, Generator};
+
+mod api {
+    use schemars::JsonSchema;
+
+    #[type_gen::export]
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    pub struct User {
+        pub name: String,
+        pub address: Address,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    pub struct Address {
+        pub street: String,
+    }
+}
+
+#[type_gen::export]
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+enum Status {
+    Active,
+    Blocked,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Page<T> {
+    items: Vec<T>,
+}
+
+type_gen::export_type!(Page<api::User>);
+
+#[test]
+fn registered_types_are_found() {
+    let names = exported_types()
+        .into_iter()
+        .map(|v| v.type_name())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "export::Page<export::api::User>",
+            "export::Status",
+            "export::api::User"
+        ]
+    );
+    assert_eq!(exported_types()[2].module_path(), "export::api");
+}
+
+#[test]
+fn registered_types_are_generated() {
+    let mut collector = ExternalTypeCollector::new();
+    let generated = gen_exported(&mut collector).unwrap();
+    let names = generated
+        .iter()
+        .map(|v| v.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Page", "Status", "User"]);
+    let dependencies = collector
+        .get_new_external_types()
+        .map(|v| v.0)
+        .collect::<Vec<_>>();
+    assert_eq!(dependencies, ["Address", "User"]);
+    gen_exported(&mut collector).unwrap();
+    assert!(collector.get_new_types().next().is_none());
+}
+
+#[test]
+fn generator_adds_registered_types() {
+    let files = Generator::new().add_exported().files().unwrap();
+    let contents = &files[0].contents;
+    for name in [
+        "type Page<'T> =",
+        "type Status =",
+        "type User =",
+        "type Address =",
+    ] {
+        assert!(contents.contains(name), "{} is missing", name);
+    }
+}
@@ -0,0 +1,826 @@
+mod common;
+
+use common::{gen_all, gen_all_from_json};
+use schemars::JsonSchema;
+use std::collections::HashMap;
+use type_gen::{
+    gen, gen_from_type, ExternalTypeCollector, NamingStrategy, OptionalFields, Serializer, TypeKind,
+};
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct FlattenedInner {
+    a: i32,
+    b: Option<String>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct WithFlattened {
+    x: String,
+    #[serde(flatten)]
+    inner: FlattenedInner,
+    #[serde(flatten)]
+    extra: HashMap<String, serde_json::Value>,
+}
+
+#[test]
+fn flattened_fields_are_merged() {
+    let generated = gen_all::<WithFlattened>(Serializer::FSharpJson);
+    assert!(generated.contains("        a : int\n        b : option<string>\n        x : string\n"));
+    assert!(generated.contains("        additional_properties : Map<string,JsonValue>\n"));
+    assert!(generated.contains("module WithFlattened =\n"));
+    assert!(generated.contains("let private knownFields = set [ \"a\"; \"b\"; \"x\" ]"));
+}
+
+#[test]
+fn all_of_parts_are_merged() {
+    let generated = gen_all_from_json(
+        r##"{
+            "title": "AllOf",
+            "allOf": [
+                { "$ref": "#/definitions/Base" },
+                {
+                    "type": "object",
+                    "required": ["y"],
+                    "properties": { "y": { "type": "string" } },
+                    "additionalProperties": { "type": "integer" }
+                }
+            ],
+            "definitions": {
+                "Base": { "type": "object", "required": ["a"], "properties": { "a": { "type": "integer" } } }
+            }
+        }"##,
+        Serializer::FSharpJson,
+    );
+    assert!(generated.contains(
+        "type AllOf =\n    {\n        a : int\n        y : string\n        additional_properties : Map<string,int>\n    }"
+    ));
+    assert!(generated.contains("Json.deserialize<int> (value.ToString())"));
+}
+
+#[test]
+fn nullable_unions_of_any_size() {
+    let generated = gen_all_from_json(
+        r##"{
+            "title": "Unions",
+            "type": "object",
+            "properties": {
+                "multi": { "type": ["string", "integer", "null"] },
+                "any": {
+                    "anyOf": [
+                        { "$ref": "#/definitions/A" },
+                        { "$ref": "#/definitions/B" },
+                        { "type": "array", "items": { "$ref": "#/definitions/A" } },
+                        { "type": "null" }
+                    ]
+                }
+            },
+            "definitions": {
+                "A": { "type": "object", "properties": { "a": { "type": "integer" } } },
+                "B": { "type": "object", "properties": { "b": { "type": "integer" } } }
+            }
+        }"##,
+        Serializer::FSharpJson,
+    );
+    assert!(generated.contains(
+        "        [<JsonField(Transform = typeof<UnionsAny.Transform>)>] any : option<UnionsAny>\n"
+    ));
+    assert!(generated.contains(
+        "        [<JsonField(Transform = typeof<UnionsMulti.Transform>)>] multi : option<UnionsMulti>\n"
+    ));
+    assert!(generated.contains("type UnionsAny =\n    | A of A\n    | B of B\n    | AArray of A[]"));
+    assert!(generated.contains("type UnionsMulti =\n    | String of string\n    | Int of int"));
+}
+
+#[test]
+fn type_lists_become_untagged_unions() {
+    let generated = gen_all_from_json(
+        r##"{
+            "title": "Plain",
+            "type": "object",
+            "required": ["value"],
+            "properties": { "value": { "type": ["string", "boolean"] } }
+        }"##,
+        Serializer::FSharpJson,
+    );
+    assert!(generated.contains(
+        "        [<JsonField(Transform = typeof<PlainValue.Transform>)>] value : PlainValue\n"
+    ));
+    assert!(generated.contains(
+        "type PlainValue =\n    | String of string\n    | Bool of bool\nmodule PlainValue ="
+    ));
+    assert!(generated.contains("(fun () -> Bool (Json.deserialize<bool> json))"));
+    assert!(generated.contains("        | Bool value -> Json.serialize value"));
+    assert!(generated.contains(
+        "        member _.toTargetType value = box (JsonValue.Parse (serialize (value :?> PlainValue)))"
+    ));
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct WithExtraFields {
+    inner: WithFlattened,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Message {
+    #[schemars(regex(pattern = "^[a-z]+$"))]
+    text: String,
+    #[schemars(range(min = 1))]
+    priority: Option<i32>,
+}
+
+#[test]
+fn fsharp_json_uses_the_functions_of_wrappers_in_records() {
+    let mut collector = ExternalTypeCollector::new();
+    collector.set_wrap_constrained(true);
+    let generated = String::from(gen_from_type::<Message>(&mut collector).unwrap());
+    assert!(generated.contains(
+        "        [<JsonField(Transform = typeof<MessagePriority.Transform>)>] priority : option<MessagePriority>\n"
+    ));
+    assert!(generated.contains(
+        "        [<JsonField(Transform = typeof<MessageText.Transform>)>] text : MessageText\n"
+    ));
+    let new_types = collector
+        .get_new_external_types()
+        .map(|v| v.1)
+        .collect::<Vec<_>>()
+        .join("\n");
+    assert!(new_types.contains(
+        r#"    type Transform() =
+        interface ITypeTransform with
+            member _.targetType () = typeof<string>
+            member _.toTargetType wrapped = box (value (wrapped :?> MessageText))
+            member _.fromTargetType unwrapped =
+                match create (unwrapped :?> string) with"#
+    ));
+}
+
+#[test]
+fn fsharp_json_uses_the_functions_of_records_with_extra_fields() {
+    let generated = gen_all::<WithExtraFields>(Serializer::FSharpJson);
+    assert!(generated.contains(
+        "        [<JsonField(Transform = typeof<WithFlattened.Transform>)>] inner : WithFlattened\n"
+    ));
+    assert!(generated.contains(
+        "        member _.fromTargetType json = box (deserialize ((json :?> JsonValue).ToString()))"
+    ));
+}
+
+#[test]
+fn fsharp_json_rejects_types_with_functions_outside_of_record_fields() {
+    let gen_json = |schema: &str| {
+        gen(
+            serde_json::from_str(schema).unwrap(),
+            &mut ExternalTypeCollector::new(),
+        )
+        .map(drop)
+    };
+    let untagged = gen_json(
+        r##"{
+            "title": "Plain",
+            "type": "object",
+            "required": ["values"],
+            "properties": {
+                "values": { "type": "array", "items": { "type": ["string", "boolean"] } }
+            }
+        }"##,
+    );
+    assert_eq!(untagged, Err(type_gen::Error::TypeCantBeNested));
+    let literals = gen_json(
+        r##"{
+            "title": "Versioned",
+            "oneOf": [
+                { "type": "object", "required": ["version"], "properties": { "version": { "$ref": "#/definitions/Version" } } }
+            ],
+            "definitions": { "Version": { "enum": ["v1", 2] } }
+        }"##,
+    );
+    assert_eq!(literals, Err(type_gen::Error::TypeCantBeNested));
+    //wrappers can only be used as field, not inside of an array
+    let wrappers = gen_json(
+        r##"{
+            "title": "Versions",
+            "type": "object",
+            "required": ["versions"],
+            "properties": {
+                "versions": { "type": "array", "items": { "$ref": "#/definitions/Version" } }
+            },
+            "definitions": { "Version": { "const": "v1" } }
+        }"##,
+    );
+    assert_eq!(wrappers, Err(type_gen::Error::TypeCantBeNested));
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+#[serde(tag = "type")]
+enum InternallyTagged {
+    A { x: i32 },
+    B(FlattenedInner),
+    C,
+}
+
+#[test]
+fn serde_tags_are_left_out_of_the_cases() {
+    let generated = gen_all::<InternallyTagged>(Serializer::FSharpJson);
+    assert!(generated.contains("type InternallyTaggedA =\n    {\n        x : int\n    }"));
+    assert!(generated.contains(
+        "[<JsonUnion(Mode = UnionMode.CaseKeyDiscriminatorField, CaseKeyField = \"type\")>]\ntype InternallyTagged =\n    | A of InternallyTaggedA\n    | B of InternallyTaggedB\n    | C"
+    ));
+    assert!(!generated.contains("type : "));
+}
+
+#[test]
+fn standalone_literals_are_checked() {
+    let mut external_types = ExternalTypeCollector::new();
+    external_types.set_validate(true);
+    let generated = String::from(
+        gen(
+            serde_json::from_str(
+                r##"{
+                    "title": "Versioned",
+                    "type": "object",
+                    "required": ["version", "kind", "ratio", "sizes"],
+                    "properties": {
+                        "version": { "const": "v1" },
+                        "kind": { "type": "integer", "enum": [3] },
+                        "ratio": { "type": "number", "const": 5 },
+                        "sizes": { "const": [1, 2] }
+                    }
+                }"##,
+            )
+            .unwrap(),
+            &mut external_types,
+        )
+        .unwrap(),
+    );
+    assert!(generated.contains(
+        "        kind : int\n        ratio : float\n        sizes : int[]\n        version : string\n"
+    ));
+    assert!(generated.contains(
+        r#"        [
+            if value.kind <> 3 then "kind must be 3"
+            if value.ratio <> 5.0 then "ratio must be 5"
+            if value.sizes <> [|1; 2|] then "sizes must be [1,2]"
+            if value.version <> "v1" then "version must be \"v1\""
+        ]"#
+    ));
+}
+
+#[test]
+fn literal_definitions_only_accept_their_value() {
+    let generated = gen_all_from_json(
+        r##"{
+            "title": "Message",
+            "type": "object",
+            "required": ["version", "only"],
+            "properties": {
+                "version": { "$ref": "#/definitions/Version" },
+                "only": { "$ref": "#/definitions/Only" }
+            },
+            "definitions": {
+                "Version": { "const": "v1" },
+                "Only": { "type": "string", "enum": ["v1"] }
+            }
+        }"##,
+        Serializer::FSharpJson,
+    );
+    assert!(generated.contains(
+        "        [<JsonField(Transform = typeof<Only.Transform>)>] only : Only\n        [<JsonField(Transform = typeof<Version.Transform>)>] version : Version\n"
+    ));
+    assert!(generated.contains(
+        r#"type Version =
+    private Version of string
+module Version =
+    let create (value : string) : Result<Version, string list> =
+        [
+            if value <> "v1" then "Version must be \"v1\""
+        ]"#
+    ));
+    assert!(generated.contains("type Only =\n    private Only of string\n"));
+    assert!(!generated.contains("JsonUnionCase"));
+}
+
+#[test]
+fn integer_enums_become_fsharp_enums() {
+    let generated = gen_all_from_json(
+        r##"{ "title": "Code", "type": "integer", "enum": [1, 2, -3] }"##,
+        Serializer::FSharpJson,
+    );
+    assert_eq!(
+        generated,
+        "type Code =\n    | Value1 = 1\n    | Value2 = 2\n    | ValueMinus3 = -3"
+    );
+    let generated = gen_all_from_json(
+        r##"{ "title": "Named", "type": "integer", "enum": [1, 2], "x-enum-varnames": ["Ok", "Bad"] }"##,
+        Serializer::FSharpJson,
+    );
+    assert!(generated.contains("    | Ok = 1\n    | Bad = 2"));
+}
+
+#[test]
+fn fsharp_json_writes_integer_enum_fields_as_numbers() {
+    let generated = gen_all_from_json(
+        r##"{
+            "title": "Response",
+            "type": "object",
+            "required": ["code"],
+            "properties": {
+                "code": { "$ref": "#/definitions/Code" },
+                "fallback": { "$ref": "#/definitions/Code" }
+            },
+            "definitions": { "Code": { "type": "integer", "enum": [1, 2] } }
+        }"##,
+        Serializer::FSharpJson,
+    );
+    assert!(generated.contains(
+        "        [<JsonField(EnumValue = EnumMode.Value)>] code : Code\n        [<JsonField(EnumValue = EnumMode.Value)>] fallback : option<Code>\n"
+    ));
+    let generated = gen_all_from_json(
+        r##"{
+            "title": "Response",
+            "type": "object",
+            "required": ["code"],
+            "properties": { "code": { "$ref": "#/definitions/Code" } },
+            "definitions": { "Code": { "type": "integer", "enum": [1, 2] } }
+        }"##,
+        Serializer::Newtonsoft,
+    );
+    assert!(!generated.contains("EnumValue"));
+}
+
+#[test]
+fn mixed_enums_become_unions_with_a_converter() {
+    let generated = gen_all_from_json(
+        r##"{ "title": "Mixed", "enum": [1, "A", true, null] }"##,
+        Serializer::FSharpJson,
+    );
+    assert!(generated.starts_with(
+        "type Mixed =\n    | Value1\n    | A\n    | True\n    | Null\nmodule Mixed =\n"
+    ));
+    assert!(generated.contains("            JsonValue.Parse \"\\\"A\\\"\", A\n"));
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+#[serde(rename_all = "kebab-case")]
+enum RenamedCases {
+    InProgress,
+    Done,
+    #[serde(rename = "2fa")]
+    TwoFactor,
+    #[serde(rename = "snake_case")]
+    Snake,
+    WithData {
+        a: i32,
+    },
+}
+
+#[test]
+fn case_names_are_valid_identifiers() {
+    let generated = gen_all::<RenamedCases>(Serializer::FSharpJson);
+    assert!(generated.contains("    | [<JsonUnionCase(\"in-progress\")>] InProgress\n"));
+    assert!(generated.contains("    | [<JsonUnionCase(\"2fa\")>] Value2fa\n"));
+    assert!(generated.contains("    | [<JsonUnionCase(\"snake_case\")>] SnakeCase\n"));
+    assert!(generated
+        .contains("    | [<JsonUnionCase(\"with-data\")>] WithData of RenamedCasesWithData"));
+    let generated = gen_all::<SimpleEnum>(Serializer::FSharpJson);
+    assert!(generated.contains("    | A\n    | B"));
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+enum SimpleEnum {
+    A,
+    B,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Page<T> {
+    items: Vec<T>,
+    title: String,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Paginated {
+    users: Page<ExternalType>,
+    names: Page<String>,
+    nested: Page<Page<ExternalType>>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct ExternalType {
+    test: String,
+}
+
+#[test]
+fn generic_types_are_generated_once() {
+    let generated = gen_all::<Paginated>(Serializer::FSharpJson);
+    assert!(generated.contains(
+        "        names : Page<string>\n        nested : Page<Page<ExternalType>>\n        users : Page<ExternalType>\n"
+    ));
+    assert_eq!(generated.matches("type Page").count(), 1);
+    assert!(generated
+        .contains("type Page<'T> =\n    {\n        items : 'T[]\n        title : string\n    }"));
+}
+
+#[test]
+fn generic_root_types_become_the_generic_type() {
+    let generated = gen_all::<Page<ExternalType>>(Serializer::FSharpJson);
+    assert!(generated
+        .contains("type Page<'T> =\n    {\n        items : 'T[]\n        title : string\n    }"));
+    assert!(generated.contains("type ExternalType ="));
+    assert!(!generated.contains("Page_for_"));
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct PrimitivePages {
+    names: Page<String>,
+    counts: Page<i32>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct SinglePrimitivePage {
+    names: Page<String>,
+}
+
+#[test]
+fn only_the_places_of_the_type_parameter_become_generic() {
+    let generated = gen_all::<PrimitivePages>(Serializer::FSharpJson);
+    assert!(generated.contains("        counts : Page<int>\n        names : Page<string>\n"));
+    assert!(generated
+        .contains("type Page<'T> =\n    {\n        items : 'T[]\n        title : string\n    }"));
+    //with a single instance, `items` can't be told apart from `title`
+    let generated = gen_all::<SinglePrimitivePage>(Serializer::FSharpJson);
+    assert!(generated.contains("        names : Page_for_String\n"));
+    assert!(generated.contains(
+        "type Page_for_String =\n    {\n        items : string[]\n        title : string\n    }"
+    ));
+    assert!(!generated.contains("'T"));
+}
+
+#[test]
+fn generic_hints_are_used() {
+    let mut external_types = ExternalTypeCollector::new();
+    external_types.add_generic_hint("UserList", "List", vec!["User".to_owned()]);
+    let generated = String::from(
+        gen(
+            serde_json::from_str(
+                r##"{
+                    "title": "Hinted",
+                    "type": "object",
+                    "required": ["users"],
+                    "properties": { "users": { "$ref": "#/definitions/UserList" } },
+                    "definitions": {
+                        "User": { "type": "object", "required": ["name"], "properties": { "name": { "type": "string" } } },
+                        "UserList": {
+                            "type": "object",
+                            "required": ["values"],
+                            "properties": { "values": { "type": "array", "items": { "$ref": "#/definitions/User" } } }
+                        }
+                    }
+                }"##,
+            )
+            .unwrap(),
+            &mut external_types,
+        )
+        .unwrap(),
+    );
+    assert!(generated.contains("        users : List<User>\n"));
+    let external_types = external_types
+        .get_new_external_types()
+        .map(|v| v.1)
+        .collect::<Vec<_>>()
+        .join("\n");
+    assert!(external_types.contains("type List<'T> =\n    {\n        values : 'T[]\n    }"));
+}
+
+const NESTED_OBJECTS: &str = r##"{
+    "title": "User",
+    "type": "object",
+    "required": ["address", "home_address", "other"],
+    "properties": {
+        "address": { "type": "object", "required": ["street"], "properties": { "street": { "type": "string" } } },
+        "home_address": { "type": "object", "required": ["city"], "properties": { "city": { "type": "string" } } },
+        "other": { "$ref": "#/definitions/UserAddress" }
+    },
+    "definitions": {
+        "UserAddress": { "type": "object", "required": ["zip"], "properties": { "zip": { "type": "string" } } }
+    }
+}"##;
+
+#[test]
+fn nested_types_do_not_overwrite_other_types() {
+    let generated = gen_all_from_json(NESTED_OBJECTS, Serializer::FSharpJson);
+    assert!(generated.contains(
+        "        address : UserAddress2\n        home_address : UserHomeAddress\n        other : UserAddress\n"
+    ));
+    assert!(generated.contains("type UserAddress2 =\n    {\n        street : string\n    }"));
+    assert!(generated.contains("type UserAddress =\n    {\n        zip : string\n    }"));
+}
+
+struct SnakeCaseNaming;
+impl NamingStrategy for SnakeCaseNaming {
+    fn name_nested_type(&self, parent: &str, part: &str) -> String {
+        format!("{}_{}", parent, part)
+    }
+}
+
+#[test]
+fn nested_types_use_the_naming_strategy_and_hints() {
+    let mut external_types = ExternalTypeCollector::new();
+    external_types.set_naming_strategy(SnakeCaseNaming);
+    external_types.add_name_hint("User", "address", "PostalAddress");
+    let generated = String::from(
+        gen(
+            serde_json::from_str(NESTED_OBJECTS).unwrap(),
+            &mut external_types,
+        )
+        .unwrap(),
+    );
+    assert!(generated
+        .contains("        address : PostalAddress\n        home_address : User_home_address\n"));
+}
+
+#[test]
+fn new_types_can_be_streamed() {
+    let mut external_types = ExternalTypeCollector::new();
+    let root = gen(
+        serde_json::from_str(NESTED_OBJECTS).unwrap(),
+        &mut external_types,
+    )
+    .unwrap()
+    .into_option()
+    .unwrap();
+    match &root.kind {
+        TypeKind::Record(fields) => assert_eq!(fields.len(), 3),
+        _ => panic!("expected a record"),
+    }
+    let mut out = Vec::new();
+    external_types.write_new_types(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("type UserAddress2 =\n    {\n        street : string\n    }\n"));
+    assert!(external_types.get_new_types().next().is_none());
+}
+
+#[test]
+fn only_changed_schemas_are_generated_again() {
+    let mut collector = ExternalTypeCollector::new();
+    gen(
+        serde_json::from_str(NESTED_OBJECTS).unwrap(),
+        &mut collector,
+    )
+    .unwrap();
+    let before = collector.get_type_with_dependencies("User");
+    let changed = NESTED_OBJECTS.replace(
+        r#""city": { "type": "string" }"#,
+        r#""city": { "type": "integer" }"#,
+    );
+    let changed: schemars::schema::RootSchema = serde_json::from_str(&changed).unwrap();
+    let mut forgotten = collector.update_schema(&changed).unwrap();
+    forgotten.sort();
+    assert_eq!(
+        forgotten,
+        ["User", "UserAddress2", "UserHomeAddress"].map(ToOwned::to_owned)
+    );
+    gen(changed, &mut collector).unwrap();
+    let after = collector.get_type_with_dependencies("User");
+    let names = after.iter().map(|v| v.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        ["UserAddress2", "UserHomeAddress", "UserAddress", "User"]
+    );
+    //the definition that didn't change is kept as is
+    assert!(std::rc::Rc::ptr_eq(&before[2], &after[2]));
+    assert!(after[1].to_string().contains("city : int"));
+}
+
+///A place to live.
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+enum DocumentedPlace {
+    Nowhere,
+    ///A city.
+    City(String),
+}
+
+///A user of the system.
+///
+///Can log in.
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Documented {
+    ///The name of the user.
+    name: String,
+    ///Where the user lives.
+    home: DocumentedPlace,
+    undocumented: bool,
+}
+
+#[test]
+fn doc_comments_are_kept() {
+    assert_eq!(
+        gen_all::<Documented>(Serializer::FSharpJson),
+        "/// A place to live.
+type DocumentedPlace =
+    | Nowhere
+    /// A city.
+    | City of string
+/// A user of the system.
+///
+/// Can log in.
+type Documented =
+    {
+        /// Where the user lives.
+        home : DocumentedPlace
+        /// The name of the user.
+        name : string
+        undocumented : bool
+    }"
+    );
+}
+
+//the derived implementations use the deprecated parts
+#[allow(deprecated)]
+mod deprecated {
+    use schemars::JsonSchema;
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    #[deprecated]
+    pub enum OldPlace {
+        Nowhere,
+        #[deprecated]
+        City(String),
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    pub struct WithDeprecated {
+        #[deprecated]
+        old: String,
+        place: OldPlace,
+    }
+}
+
+#[test]
+fn deprecated_parts_are_obsolete() {
+    assert_eq!(
+        gen_all::<deprecated::WithDeprecated>(Serializer::FSharpJson),
+        r#"[<System.Obsolete("Deprecated in the schema")>]
+type OldPlace =
+    | Nowhere
+    | [<System.Obsolete("Deprecated in the schema")>] City of string
+type WithDeprecated =
+    {
+        [<System.Obsolete("Deprecated in the schema")>] old : string
+        place : OldPlace
+    }"#
+    );
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct WithDefaults {
+    name: String,
+    #[serde(default)]
+    count: i32,
+    #[serde(default)]
+    tags: Vec<Option<String>>,
+    nickname: Option<String>,
+}
+
+#[test]
+fn fields_that_can_be_missing_are_optional() {
+    let generated = gen_all::<WithDefaults>(Serializer::FSharpJson);
+    assert!(generated.contains(
+        "        count : option<int>\n        name : string\n        nickname : option<string>\n        tags : option<option<string>[]>\n"
+    ));
+}
+
+#[test]
+fn defaults_fall_back_to_options_without_support_of_the_serializer() {
+    let mut external_types = ExternalTypeCollector::new();
+    external_types.set_optional_fields(OptionalFields::Default);
+    let generated = String::from(gen_from_type::<WithDefaults>(&mut external_types).unwrap());
+    assert!(generated.contains("        count : option<int>\n"));
+    let mut external_types = ExternalTypeCollector::new();
+    external_types.set_optional_fields(OptionalFields::ValueOption);
+    let generated = String::from(gen_from_type::<WithDefaults>(&mut external_types).unwrap());
+    assert!(generated.contains("        count : option<int>\n"));
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct SignUp {
+    #[schemars(length(min = 3, max = 20), regex(pattern = r"^[a-z_]+$"))]
+    name: String,
+    #[schemars(range(min = 18))]
+    age: i32,
+    #[schemars(range(max = 0.5))]
+    ratio: f64,
+    #[schemars(length(min = 1))]
+    tags: Vec<String>,
+    #[schemars(length(max = 5))]
+    nickname: Option<String>,
+    plain: bool,
+}
+
+#[test]
+fn constraints_are_validated() {
+    let mut external_types = ExternalTypeCollector::new();
+    external_types.set_validate(true);
+    let generated = String::from(gen_from_type::<SignUp>(&mut external_types).unwrap());
+    assert!(generated.ends_with(
+        r#"    let validate (value : SignUp) : Result<SignUp, string list> =
+        [
+            if value.age < 18 then "age must be at least 18"
+            if value.name.Length < 3 then "name must be at least 3 characters long"
+            if value.name.Length > 20 then "name must be at most 20 characters long"
+            if not (System.Text.RegularExpressions.Regex.IsMatch(value.name, "^[a-z_]+$")) then "name must match ^[a-z_]+$"
+            match value.nickname with
+            | Some v ->
+                if v.Length > 5 then "nickname must be at most 5 characters long"
+            | _ -> ()
+            if value.ratio > 0.5 then "ratio must be at most 0.5"
+            if value.tags.Length < 1 then "tags must have at least 1 items"
+        ]
+        |> function
+            | [] -> Ok value
+            | errors -> Error errors"#
+    ));
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct CheckedPage<T> {
+    #[schemars(length(min = 1))]
+    items: Vec<T>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct CheckedPages {
+    users: CheckedPage<ExternalType>,
+    names: CheckedPage<String>,
+}
+
+#[test]
+fn generic_types_are_validated_as_generic_types() {
+    let mut external_types = ExternalTypeCollector::new();
+    external_types.set_validate(true);
+    let generated = String::from(gen_from_type::<CheckedPages>(&mut external_types).unwrap());
+    assert!(generated.contains("        names : CheckedPage<string>\n"));
+    let new_types = external_types
+        .get_new_external_types()
+        .map(|v| v.1)
+        .collect::<Vec<_>>()
+        .join("\n");
+    assert!(new_types.contains(
+        "    let validate (value : CheckedPage<'T>) : Result<CheckedPage<'T>, string list> =\n"
+    ));
+}
+
+#[test]
+fn constrained_values_can_get_a_type_of_their_own() {
+    let mut external_types = ExternalTypeCollector::new();
+    external_types.set_wrap_constrained(true);
+    external_types.set_validate(true);
+    let generated = String::from(gen_from_type::<SignUp>(&mut external_types).unwrap());
+    let new_types = external_types
+        .get_new_external_types()
+        .map(|v| v.1)
+        .collect::<Vec<_>>()
+        .join("\n");
+    assert!(generated.contains(
+        "        [<JsonField(Transform = typeof<SignUpAge.Transform>)>] age : SignUpAge\n        [<JsonField(Transform = typeof<SignUpName.Transform>)>] name : SignUpName\n        [<JsonField(Transform = typeof<SignUpNickname.Transform>)>] nickname : option<SignUpNickname>\n        plain : bool\n        [<JsonField(Transform = typeof<SignUpRatio.Transform>)>] ratio : SignUpRatio\n        tags : string[]\n"
+    ));
+    //the constraints are checked by the wrapper types, only the array is left to validate
+    assert!(generated.contains("            if value.tags.Length < 1 then"));
+    assert!(!generated.contains("value.name"));
+    assert!(new_types.contains(
+        r#"type SignUpAge =
+    private SignUpAge of int
+module SignUpAge =
+    let create (value : int) : Result<SignUpAge, string list> =
+        [
+            if value < 18 then "age must be at least 18"
+        ]
+        |> function
+            | [] -> Ok (SignUpAge value)
+            | errors -> Error errors
+
+    let value (SignUpAge value) : int = value
+
+    let deserialize (json : string) : SignUpAge =
+        match create (Json.deserialize<int> json) with"#
+    ));
+}
@@ -0,0 +1,131 @@
+use schemars::JsonSchema;
+use type_gen::{Config, Generator};
+
+mod shared {
+    use schemars::JsonSchema;
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    pub struct Address {
+        pub street: String,
+    }
+}
+
+mod users {
+    use schemars::JsonSchema;
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    pub struct User {
+        pub name: String,
+        pub address: super::shared::Address,
+    }
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Order {
+    user: users::User,
+    delivery: shared::Address,
+}
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "type_gen_generator_{}_{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn shared_types_are_generated_once() {
+    let files = Generator::new()
+        .add::<Order>()
+        .add::<users::User>()
+        .add::<shared::Address>()
+        .files()
+        .unwrap();
+    assert_eq!(files.len(), 1);
+    let contents = &files[0].contents;
+    assert_eq!(contents.matches("type Address =").count(), 1);
+    assert_eq!(contents.matches("type User =").count(), 1);
+    let position = |name: &str| contents.find(name).unwrap();
+    assert!(position("type Address =") < position("type User ="));
+    assert!(position("type User =") < position("type Order ="));
+}
+
+#[test]
+fn writes_a_single_file() {
+    let dir = temp_dir("single");
+    let path = dir.join("Bindings.fs");
+    let written = Generator::new()
+        .add::<Order>()
+        .rerun_if_changed("src")
+        .write_to(&path)
+        .unwrap();
+    assert_eq!(written.len(), 1);
+    assert_eq!(written[0], path);
+    assert!(std::fs::read_to_string(&path)
+        .unwrap()
+        .starts_with("namespace Types\n"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn writes_split_files_to_a_directory() {
+    let dir = temp_dir("split");
+    let config = Config::from_toml("[output]\nmodule = \"Api\"\nsplit = true").unwrap();
+    let written = Generator::with_config(config)
+        .add::<Order>()
+        .add::<users::User>()
+        .add::<shared::Address>()
+        .write_to(&dir)
+        .unwrap();
+    let names = written
+        .iter()
+        .map(|v| v.file_name().unwrap().to_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Shared.fs", "Users.fs", "Generator.fs"]);
+    assert!(std::fs::read_to_string(&written[2])
+        .unwrap()
+        .contains("open Api.Users\n"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+mod accounts {
+    use schemars::JsonSchema;
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    pub enum User {
+        Address { x: i32 },
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    pub struct UserAddress {
+        pub street: String,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    pub struct Other {
+        pub a: UserAddress,
+    }
+}
+
+#[test]
+fn nested_types_make_way_for_the_types_of_later_schemas() {
+    let files = Generator::new()
+        .add::<accounts::User>()
+        .add::<accounts::Other>()
+        .files()
+        .unwrap();
+    let contents = &files[0].contents;
+    assert!(contents.contains("type UserAddress =\n    {\n        street : string\n    }"));
+    assert!(contents.contains("type UserAddress2 =\n    {\n        x : int\n    }"));
+    assert!(contents.contains("    | Address of UserAddress2\n"));
+    assert!(contents.contains("        a : UserAddress\n"));
+}
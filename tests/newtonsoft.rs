@@ -0,0 +1,156 @@
+mod common;
+
+use common::gen_all;
+use schemars::JsonSchema;
+use std::collections::HashMap;
+use type_gen::{gen_from_type, Config, ExternalTypeCollector, FSharpWriter, Serializer};
+
+#[derive(JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[allow(dead_code)]
+enum Status {
+    InProgress,
+    Failed(String),
+}
+
+#[derive(JsonSchema)]
+#[serde(tag = "kind")]
+#[allow(dead_code)]
+enum Event {
+    Started,
+    Moved { x: i32 },
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Log {
+    event: Event,
+    #[serde(flatten)]
+    extra: HashMap<String, serde_json::Value>,
+}
+
+#[test]
+fn unions_get_a_converter() {
+    assert_eq!(
+        gen_all::<Status>(Serializer::Newtonsoft),
+        r#"type Status =
+    | InProgress
+    | Failed of string
+module Status =
+    type Converter() =
+        inherit JsonConverter<Status>()
+        override _.ReadJson(reader, _, _, _, serializer) =
+            let token = JToken.ReadFrom reader
+            let name, value =
+                match token with
+                | :? JObject as token when token.Count = 1 ->
+                    let property = token.Properties() |> Seq.head
+                    property.Name, Some property.Value
+                | _ when token.Type = JTokenType.String -> token.ToObject<string>(), None
+                | _ -> "", None
+            match name, value with
+            | "in_progress", None -> InProgress
+            | "failed", Some value -> Failed (value.ToObject<string>(serializer))
+            | _ -> raise (JsonSerializationException(sprintf "Could not read %O as Status" token))
+        override _.WriteJson(writer, value, serializer) =
+            match value with
+            | InProgress -> writer.WriteValue "in_progress"
+            | Failed value -> JObject(JProperty("failed", JToken.FromObject(value, serializer))).WriteTo writer"#
+    );
+    let generated = gen_all::<Log>(Serializer::Newtonsoft);
+    assert!(generated.contains(
+        "            match token.Value<string>(\"kind\") with
+            | \"Started\" -> Started
+            | \"Moved\" -> Moved (token.ToObject<EventMoved>(serializer))
+"
+    ));
+    assert!(generated.contains(
+        "            | Moved value -> JObject(JProperty(\"kind\", \"Moved\"), JObject.FromObject(value, serializer).Properties()).WriteTo writer\n"
+    ));
+}
+
+#[test]
+fn config_picks_the_serializer() {
+    let config = Config::from_toml(
+        "[output]\nserializer = \"newtonsoft\"\nopens = [\"System\", \"Newtonsoft.Json\"]",
+    )
+    .unwrap();
+    let mut collector = ExternalTypeCollector::from_config(&config);
+    let mut writer = FSharpWriter::from_config(&config);
+    writer.add_type::<Log>(&mut collector).unwrap();
+    let contents = &writer.files()[0].contents;
+    assert!(contents.starts_with(
+        "namespace Types\n\nopen Newtonsoft.Json\nopen Newtonsoft.Json.Linq\nopen System\n\n"
+    ));
+    assert!(contents.contains("        additional_properties : Map<string,JToken>\n"));
+    assert!(Config::from_toml("[output]\nserializer = \"yaml\"").is_err());
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Retry {
+    #[serde(default = "default_attempts")]
+    attempts: i32,
+    #[serde(default)]
+    delays: Vec<f64>,
+}
+
+fn default_attempts() -> i32 {
+    3
+}
+
+#[test]
+fn simple_defaults_are_filled_in() {
+    let config =
+        Config::from_toml("[output]\nserializer = \"newtonsoft\"\noptional_fields = \"default\"")
+            .unwrap();
+    let mut collector = ExternalTypeCollector::from_config(&config);
+    let generated = String::from(gen_from_type::<Retry>(&mut collector).unwrap());
+    assert!(generated.contains(
+        "        [<System.ComponentModel.DefaultValue(3)>] [<JsonProperty(DefaultValueHandling = DefaultValueHandling.Populate)>] attempts : int\n"
+    ));
+    //Newtonsoft.Json can only fill in defaults that fit in an attribute
+    assert!(generated.contains(
+        "        [<JsonProperty(NullValueHandling = NullValueHandling.Ignore)>] delays : option<float[]>\n"
+    ));
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Delay {
+    seconds: Option<f64>,
+}
+
+#[test]
+fn fields_that_can_be_missing_are_left_out_when_none() {
+    let config =
+        Config::from_toml("[output]\nserializer = \"newtonsoft\"\noptional_fields = \"voption\"")
+            .unwrap();
+    let mut collector = ExternalTypeCollector::from_config(&config);
+    let generated = String::from(gen_from_type::<Retry>(&mut collector).unwrap());
+    assert!(generated.contains(
+        "        [<JsonProperty(NullValueHandling = NullValueHandling.Ignore)>] attempts : option<int>\n"
+    ));
+    //`None` of a field that is an `option` in Rust as well is written as `null`, like serde does
+    let generated = gen_all::<Delay>(Serializer::Newtonsoft);
+    assert!(generated.contains("        seconds : option<float>\n"));
+    assert!(!generated.contains("NullValueHandling"));
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+enum TestEnum {
+    A,
+    B(f32, i64),
+}
+
+#[test]
+fn tuple_cases_are_read_and_written_as_arrays() {
+    let generated = gen_all::<TestEnum>(Serializer::Newtonsoft);
+    assert!(generated.contains(
+        "            | \"B\", Some (:? JArray as value) when value.Count = 2 -> B (value.[0].ToObject<float>(serializer), value.[1].ToObject<int>(serializer))\n"
+    ));
+    assert!(generated.contains(
+        "            | B (value1, value2) -> JObject(JProperty(\"B\", JArray(JToken.FromObject(value1, serializer), JToken.FromObject(value2, serializer)))).WriteTo writer"
+    ));
+}
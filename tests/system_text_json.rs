@@ -0,0 +1,130 @@
+mod common;
+
+use common::{gen_all, gen_all_from_json};
+use schemars::JsonSchema;
+use type_gen::{Config, ExternalTypeCollector, FSharpWriter, Serializer};
+
+#[derive(JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[allow(dead_code)]
+enum Status {
+    InProgress,
+    Failed(String),
+}
+
+#[derive(JsonSchema)]
+#[serde(tag = "kind")]
+#[allow(dead_code)]
+enum Event {
+    Started,
+    Moved { x: i32 },
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Log {
+    status: Status,
+    event: Event,
+}
+
+#[test]
+fn tagged_unions_get_the_serde_encoding() {
+    let generated = gen_all::<Log>(Serializer::SystemTextJson);
+    assert!(generated.contains(
+        "[<JsonFSharpConverter(BaseUnionEncoding = JsonUnionEncoding.ExternalTag ||| JsonUnionEncoding.UnwrapFieldlessTags ||| JsonUnionEncoding.UnwrapSingleFieldCases)>]
+type Status =
+    | [<JsonName(\"in_progress\")>] InProgress
+    | [<JsonName(\"failed\")>] Failed of string
+"
+    ));
+    assert!(generated.contains(
+        "[<JsonFSharpConverter(BaseUnionEncoding = JsonUnionEncoding.InternalTag ||| JsonUnionEncoding.UnwrapRecordCases, UnionTagName = \"kind\")>]
+type Event =
+"
+    ));
+    assert!(generated.contains("[<JsonFSharpConverter>]\ntype Log =\n"));
+}
+
+#[test]
+fn untagged_and_literal_unions_get_a_converter() {
+    let schema = r##"{
+        "title": "Mixed",
+        "type": "object",
+        "properties": {
+            "either": { "anyOf": [ { "type": "string" }, { "type": "integer" } ] },
+            "odd": { "$ref": "#/definitions/Odd" }
+        },
+        "definitions": {
+            "Odd": { "enum": [null, 1.5, "a"] }
+        }
+    }"##;
+    let generated = gen_all_from_json(schema, Serializer::SystemTextJson);
+    assert!(generated.contains(
+        "type MixedEither =
+    | String of string
+    | Int of int
+module MixedEither =
+"
+    ));
+    assert!(generated.contains(
+        "    type Converter() =
+        inherit JsonConverter<MixedEither>()
+        override _.Read(reader, _, options) =
+            use document = JsonDocument.ParseValue(&reader)
+            let json = document.RootElement.GetRawText()
+            let attempts =
+                [
+                    (fun () -> String (JsonSerializer.Deserialize<string>(json, options)))
+                    (fun () -> Int (JsonSerializer.Deserialize<int>(json, options)))
+                ]
+"
+    ));
+    assert!(generated.contains(
+        "            [
+                \"null\", Null
+                \"1.5\", Value1_5
+                \"\\\"a\\\"\", A
+            ]
+"
+    ));
+}
+
+#[test]
+fn config_opens_the_namespaces() {
+    let config = Config::from_toml("[output]\nserializer = \"system-text-json\"").unwrap();
+    let mut collector = ExternalTypeCollector::from_config(&config);
+    let mut writer = FSharpWriter::from_config(&config);
+    writer.add_type::<Log>(&mut collector).unwrap();
+    assert!(writer.files()[0].contents.starts_with(
+        "namespace Types\n\nopen System.Text.Json\nopen System.Text.Json.Serialization\n\n"
+    ));
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Tagged {
+    name: String,
+    #[serde(flatten)]
+    extra: std::collections::HashMap<String, serde_json::Value>,
+}
+
+#[test]
+fn extra_fields_become_extension_data() {
+    let generated = gen_all::<Tagged>(Serializer::SystemTextJson);
+    assert!(generated.contains(
+        "        [<JsonExtensionData>] additional_properties : System.Collections.Generic.Dictionary<string, JsonElement>\n"
+    ));
+}
+
+#[test]
+fn literals_are_compared_after_decoding_them() {
+    let generated = gen_all_from_json(
+        r##"{ "title": "Greeting", "enum": ["héllo", 1] }"##,
+        Serializer::SystemTextJson,
+    );
+    assert!(generated.contains("                \"\\\"héllo\\\"\", Héllo\n"));
+    assert!(generated.contains("            use expected = JsonDocument.Parse(value : string)\n"));
+    assert!(generated.contains(
+        "            | JsonValueKind.String, JsonValueKind.String -> a.GetString() = b.GetString()\n"
+    ));
+}
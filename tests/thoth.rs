@@ -0,0 +1,298 @@
+mod common;
+
+use common::gen_all;
+use schemars::JsonSchema;
+use std::collections::HashMap;
+use type_gen::{
+    gen_from_type, Config, ExternalTypeCollector, FSharpWriter, OptionalFields, Serializer,
+};
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Account {
+    name: String,
+    age: Option<i32>,
+    scores: HashMap<String, f64>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+enum Shape {
+    Empty,
+    Circle(f64),
+}
+
+#[derive(JsonSchema)]
+#[serde(tag = "type")]
+#[allow(dead_code)]
+enum Event {
+    Started,
+    Moved { x: i32 },
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Page<T> {
+    items: Vec<T>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Tree {
+    children: Vec<Tree>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Everything {
+    shape: Shape,
+    event: Event,
+    page: Page<Tree>,
+}
+
+#[test]
+fn records_get_decoders_and_encoders() {
+    assert_eq!(
+        gen_all::<Account>(Serializer::Thoth),
+        r#"type Account =
+    {
+        age : option<int>
+        name : string
+        scores : Map<string,float>
+    }
+module Account =
+    let decoder : Decoder<Account> =
+        Decode.object (fun get ->
+            { age = get.Optional.Field "age" Decode.int
+              name = get.Required.Field "name" Decode.string
+              scores = get.Required.Field "scores" (Decode.dict Decode.float) })
+
+    let encodeFields (value : Account) : (string * JsonValue) list =
+        [ "age", (Encode.option Encode.int) value.age
+          "name", Encode.string value.name
+          "scores", (Map.map (fun _ -> Encode.float) >> Encode.dict) value.scores ]
+
+    let encode (value : Account) : JsonValue =
+        Encode.object (encodeFields value)"#
+    );
+}
+
+#[test]
+fn unions_follow_the_serde_representation() {
+    let generated = gen_all::<Everything>(Serializer::Thoth);
+    assert!(generated.contains(
+        r#"    let decoder : Decoder<Shape> =
+        Decode.oneOf [
+            Decode.string
+            |> Decode.andThen (function
+                | "Empty" -> Decode.succeed Empty
+                | other -> Decode.fail (sprintf "Could not read %A as Shape" other))
+            Decode.field "Circle" Decode.float |> Decode.map Circle
+        ]
+"#
+    ));
+    assert!(generated
+        .contains("        | Circle value -> Encode.object [ \"Circle\", Encode.float value ]\n"));
+    assert!(generated.contains(
+        r#"    let decoder : Decoder<Event> =
+        Decode.field "type" Decode.string
+        |> Decode.andThen (function
+            | "Started" -> Decode.succeed Started
+            | "Moved" -> EventMoved.decoder |> Decode.map Moved
+"#
+    ));
+    assert!(generated.contains(
+        "        | Moved value -> Encode.object ((\"type\", Encode.string \"Moved\") :: EventMoved.encodeFields value)\n"
+    ));
+}
+
+#[test]
+fn generic_and_recursive_types() {
+    let generated = gen_all::<Everything>(Serializer::Thoth);
+    assert!(generated.contains("    let decoder (decodeT : Decoder<'T>) : Decoder<Page<'T>> =\n"));
+    assert!(generated.contains(
+        "            { items = get.Required.Field \"items\" (Decode.array decodeT) })\n"
+    ));
+    assert!(generated.contains(
+        "              page = get.Required.Field \"page\" (Page.decoder Tree.decoder)\n"
+    ));
+    assert!(generated.contains("          \"page\", (Page.encode Tree.encode) value.page\n"));
+    assert!(generated.contains(
+        "            { children = get.Required.Field \"children\" (Decode.array (fun path value -> decoder path value)) })\n"
+    ));
+    assert!(generated.contains("    let rec decoder : Decoder<Tree> =\n"));
+    assert!(generated
+        .contains("    let rec encodeFields (value : Tree) : (string * JsonValue) list =\n"));
+}
+
+#[test]
+fn config_enables_the_codecs() {
+    let config = Config::from_toml("[output]\nserializer = \"thoth\"").unwrap();
+    let mut collector = ExternalTypeCollector::from_config(&config);
+    let mut writer = FSharpWriter::from_config(&config);
+    writer.add_type::<Account>(&mut collector).unwrap();
+    let contents = &writer.files()[0].contents;
+    assert!(contents.starts_with("namespace Types\n\nopen Thoth.Json\n\n"));
+    assert!(contents.contains("    let decoder : Decoder<Account> =\n"));
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Settings {
+    #[serde(default = "default_retries")]
+    retries: i32,
+    #[serde(default)]
+    verbose: bool,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+fn default_retries() -> i32 {
+    3
+}
+
+#[test]
+fn defaults_are_filled_in_by_the_decoder() {
+    let mut collector = ExternalTypeCollector::new();
+    collector.set_serializer(Serializer::Thoth);
+    collector.set_optional_fields(OptionalFields::Default);
+    let generated = String::from(gen_from_type::<Settings>(&mut collector).unwrap());
+    assert!(generated
+        .contains("        retries : int\n        tags : string[]\n        verbose : bool\n"));
+    assert!(generated.contains(
+        "{ retries = get.Optional.Field \"retries\" Decode.int |> Option.defaultWith (fun () -> Decode.unsafeFromString Decode.int \"3\")\n"
+    ));
+    assert!(generated.contains(
+        "tags = get.Optional.Field \"tags\" (Decode.array Decode.string) |> Option.defaultWith (fun () -> Decode.unsafeFromString (Decode.array Decode.string) \"[]\")\n"
+    ));
+}
+
+#[test]
+fn voption_fields_are_coded() {
+    let mut collector = ExternalTypeCollector::new();
+    collector.set_serializer(Serializer::Thoth);
+    collector.set_optional_fields(OptionalFields::ValueOption);
+    let generated = String::from(gen_from_type::<Settings>(&mut collector).unwrap());
+    assert!(generated.contains("        retries : voption<int>\n"));
+    assert!(generated.contains(
+        "{ retries = get.Optional.Field \"retries\" Decode.int |> ValueOption.ofOption\n"
+    ));
+    assert!(generated.contains(
+        "@ (value.retries |> ValueOption.toList |> List.map (fun value -> \"retries\", Encode.int value))\n"
+    ));
+}
+
+#[test]
+fn missing_fields_are_left_out_instead_of_written_as_null() {
+    let generated = gen_all::<Settings>(Serializer::Thoth);
+    assert!(generated.contains("        retries : option<int>\n"));
+    assert!(generated.contains(
+        r#"    let encodeFields (value : Settings) : (string * JsonValue) list =
+        []
+        @ (value.retries |> Option.toList |> List.map (fun value -> "retries", Encode.int value))
+        @ (value.tags |> Option.toList |> List.map (fun value -> "tags", (Array.map Encode.string >> Encode.array) value))
+        @ (value.verbose |> Option.toList |> List.map (fun value -> "verbose", Encode.bool value))
+"#
+    ));
+    //fields that are an `option` in Rust as well accept `null`
+    assert!(gen_all::<Account>(Serializer::Thoth)
+        .contains("        [ \"age\", (Encode.option Encode.int) value.age\n"));
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Contact {
+    #[schemars(regex(pattern = r"^\S+@\S+$"))]
+    email: String,
+}
+
+#[test]
+fn wrapper_types_are_decoded_through_create() {
+    let mut collector = ExternalTypeCollector::new();
+    collector.set_serializer(Serializer::Thoth);
+    collector.set_wrap_constrained(true);
+    let generated = String::from(gen_from_type::<Contact>(&mut collector).unwrap());
+    assert!(generated.contains("        email : ContactEmail\n"));
+    assert!(generated.contains("get.Required.Field \"email\" ContactEmail.decoder"));
+    let wrapper = collector
+        .get_new_external_types()
+        .map(|v| v.1)
+        .collect::<String>();
+    assert!(wrapper.contains(
+        "    let decoder : Decoder<ContactEmail> =
+        Decode.string
+        |> Decode.andThen (fun value ->
+            match create value with
+            | Ok value -> Decode.succeed value
+            | Error errors -> Decode.fail (String.concat \", \" errors))"
+    ));
+    assert!(wrapper.contains("        | ContactEmail value -> Encode.string value"));
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+enum TestEnum {
+    A,
+    D,
+    B(f32, i64),
+    C { test: f32, test2: String },
+    E(SimpleEnum),
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+enum SimpleEnum {
+    A,
+    B,
+    C,
+}
+
+#[test]
+fn tuple_cases_are_coded_item_by_item() {
+    let generated = gen_all::<TestEnum>(Serializer::Thoth);
+    assert!(generated.contains(
+        "            Decode.field \"B\" (Decode.tuple2 Decode.float Decode.int) |> Decode.map B\n"
+    ));
+    assert!(generated.contains(
+        "        | B (value1, value2) -> Encode.object [ \"B\", (Encode.tuple2 Encode.float Encode.int) (value1, value2) ]\n"
+    ));
+    assert!(
+        generated.contains("        | E value -> Encode.object [ \"E\", SimpleEnum.encode value ]")
+    );
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Forest {
+    children: Vec<Node>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+enum Node {
+    Leaf(i32),
+    Sub(Forest),
+}
+
+#[test]
+fn mutually_recursive_types_share_a_recursive_module() {
+    let config = Config::from_toml("[output]\nserializer = \"thoth\"").unwrap();
+    let mut collector = ExternalTypeCollector::from_config(&config);
+    let mut writer = FSharpWriter::from_config(&config);
+    writer.add_type::<Forest>(&mut collector).unwrap();
+    let contents = &writer.files()[0].contents;
+    assert!(
+        contents.contains("type Node =\n    | Leaf of int\n    | Sub of Forest\nand Forest =\n")
+    );
+    assert!(contents.contains(
+        "[<AutoOpen>]\nmodule rec NodeFunctions =\n    module Node =\n        let decoder : Decoder<Node> =\n"
+    ));
+    assert!(contents.contains("\n    module Forest =\n        let decoder : Decoder<Forest> =\n"));
+    //neither decoder is done when the other one is built
+    assert!(contents.contains(
+        "Decode.field \"Sub\" (fun path value -> Forest.decoder path value) |> Decode.map Sub\n"
+    ));
+    assert!(contents.contains(
+        "get.Required.Field \"children\" (Decode.array (fun path value -> Node.decoder path value))"
+    ));
+}
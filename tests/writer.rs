@@ -0,0 +1,215 @@
+use schemars::JsonSchema;
+use type_gen::{ExternalTypeCollector, FSharpWriter, FileHeader, Grouping, RenderCache};
+
+mod users {
+    use schemars::JsonSchema;
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    pub struct User {
+        pub name: String,
+        pub address: super::shared::Address,
+    }
+}
+
+mod shared {
+    use schemars::JsonSchema;
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    pub struct Address {
+        pub street: String,
+    }
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Root {
+    user: users::User,
+}
+
+#[test]
+fn writes_a_single_file() {
+    let mut collector = ExternalTypeCollector::new();
+    let mut writer = FSharpWriter::new(FileHeader::Namespace("Api".to_owned()));
+    writer.add_open("System");
+    writer.add_type::<users::User>(&mut collector).unwrap();
+    let files = writer.files();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].name, "Types.fs");
+    assert!(files[0]
+        .contents
+        .starts_with("namespace Api\n\nopen FSharp.Json\nopen System\n\ntype Address ="));
+    assert!(files[0].contents.contains("type User ="));
+    assert_eq!(
+        writer.fsproj_items(),
+        "    <Compile Include=\"Types.fs\" />\n"
+    );
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct WithDeprecated {
+    #[deprecated]
+    old: String,
+}
+
+#[test]
+fn deprecation_warnings_are_turned_off_for_the_generated_code() {
+    let mut collector = ExternalTypeCollector::new();
+    let mut writer = FSharpWriter::new(FileHeader::Namespace("Api".to_owned()));
+    writer.add_type::<WithDeprecated>(&mut collector).unwrap();
+    assert!(writer.files()[0]
+        .contents
+        .starts_with("namespace Api\n\n#nowarn \"44\"\nopen FSharp.Json\n\ntype WithDeprecated ="));
+}
+
+#[test]
+fn deprecation_warnings_are_only_turned_off_where_needed() {
+    let mut collector = ExternalTypeCollector::new();
+    let mut writer = FSharpWriter::new(FileHeader::Namespace("Api".to_owned()));
+    writer.set_grouping(Grouping::Custom(Box::new(|name| match name {
+        "WithDeprecated" => "Old".to_owned(),
+        _ => "New".to_owned(),
+    })));
+    writer.add_type::<WithDeprecated>(&mut collector).unwrap();
+    writer.add_type::<Root>(&mut collector).unwrap();
+    let files = writer.files();
+    assert!(files[0].contents.contains("#nowarn \"44\""));
+    assert!(!files[1].contents.contains("#nowarn"));
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Employee {
+    name: String,
+    team: Option<Box<Team>>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Team {
+    lead: Employee,
+    members: Vec<Employee>,
+}
+
+#[test]
+fn types_that_use_each_other_become_a_recursive_group() {
+    let mut collector = ExternalTypeCollector::new();
+    let mut writer = FSharpWriter::new(FileHeader::Namespace("Api".to_owned()));
+    writer.add_type::<Employee>(&mut collector).unwrap();
+    let contents = &writer.files()[0].contents;
+    assert!(contents.contains("\ntype Team =\n"));
+    assert!(contents.contains("\nand Employee =\n"));
+    assert_eq!(contents.matches("type ").count(), 1);
+}
+
+#[test]
+fn splits_files_by_module_path() {
+    let mut collector = ExternalTypeCollector::new();
+    let mut writer = FSharpWriter::new(FileHeader::Module("Api".to_owned()));
+    writer.set_grouping(Grouping::ByModulePath);
+    writer.add_type::<shared::Address>(&mut collector).unwrap();
+    writer.add_type::<users::User>(&mut collector).unwrap();
+    writer.add_type::<Root>(&mut collector).unwrap();
+    let files = writer.files();
+    let names = files.iter().map(|v| v.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["Shared.fs", "Users.fs", "Writer.fs"]);
+    assert!(files[1]
+        .contents
+        .starts_with("module Api.Users\n\nopen FSharp.Json\nopen Api.Shared\n\ntype User ="));
+    assert!(files[2].contents.contains("open Api.Users\n"));
+}
+
+#[test]
+fn orders_files_by_their_dependencies() {
+    let mut collector = ExternalTypeCollector::new();
+    let mut writer = FSharpWriter::new(FileHeader::Namespace("Api".to_owned()));
+    writer.set_grouping(Grouping::Custom(Box::new(|name| match name {
+        "Address" => "Late".to_owned(),
+        _ => "Early".to_owned(),
+    })));
+    writer.add_type::<Root>(&mut collector).unwrap();
+    assert_eq!(
+        writer.fsproj_items(),
+        "    <Compile Include=\"Late.fs\" />\n    <Compile Include=\"Early.fs\" />\n"
+    );
+}
+
+#[test]
+fn streamed_files_match_the_generated_ones() {
+    let mut collector = ExternalTypeCollector::new();
+    let mut writer = FSharpWriter::new(FileHeader::Module("Api".to_owned()));
+    writer.set_grouping(Grouping::ByModulePath);
+    writer.add_type::<users::User>(&mut collector).unwrap();
+    writer.add_type::<Root>(&mut collector).unwrap();
+    let dir = std::env::temp_dir().join(format!("type_gen_writer_{}", std::process::id()));
+    let paths = writer.write_to_dir(&dir).unwrap();
+    for (path, file) in paths.iter().zip(writer.files()) {
+        assert_eq!(std::fs::read_to_string(path).unwrap(), file.contents);
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn finds_stale_files() {
+    let mut collector = ExternalTypeCollector::new();
+    let mut writer = FSharpWriter::new(FileHeader::Namespace("Api".to_owned()));
+    writer.add_type::<users::User>(&mut collector).unwrap();
+    let dir = std::env::temp_dir().join(format!("type_gen_check_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let stale = writer.check_dir(&dir).unwrap();
+    assert_eq!(stale.len(), 1);
+    assert!(stale[0].diff.contains("+type User =\n"));
+
+    writer.write_to_dir(&dir).unwrap();
+    assert!(writer.check_dir(&dir).unwrap().is_empty());
+
+    let path = dir.join("Types.fs");
+    let outdated = std::fs::read_to_string(&path)
+        .unwrap()
+        .replace("street : string", "street : int");
+    std::fs::write(&path, outdated).unwrap();
+    let stale = writer.check_dir(&dir).unwrap();
+    assert_eq!(stale[0].path, path);
+    assert!(stale[0]
+        .diff
+        .contains("-        street : int\n+        street : string\n"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn only_changed_types_are_rendered_again() {
+    let mut collector = ExternalTypeCollector::new();
+    let mut cache = RenderCache::default();
+    let dir = std::env::temp_dir().join(format!("type_gen_cache_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let write = |schema: schemars::schema::RootSchema,
+                 collector: &mut ExternalTypeCollector,
+                 cache: &mut RenderCache| {
+        let mut writer = FSharpWriter::new(FileHeader::Namespace("Api".to_owned()));
+        writer.add_schema(schema, "writer", collector).unwrap();
+        writer.write_changed_to_dir(&dir, cache).unwrap()
+    };
+    let schema = schemars::schema_for!(Root);
+    assert_eq!(write(schema.clone(), &mut collector, &mut cache).len(), 1);
+    assert_eq!(cache.last_rendered(), ["Address", "User", "Root"]);
+
+    assert!(write(schema.clone(), &mut collector, &mut cache).is_empty());
+    assert!(cache.last_rendered().is_empty());
+
+    let mut changed = schema;
+    let mut address = serde_json::to_value(&changed.definitions["Address"]).unwrap();
+    address["properties"]["street"] = serde_json::json!({ "type": "integer" });
+    changed.definitions.insert(
+        "Address".to_owned(),
+        serde_json::from_value(address).unwrap(),
+    );
+    collector.update_schema(&changed).unwrap();
+    assert_eq!(write(changed, &mut collector, &mut cache).len(), 1);
+    assert_eq!(cache.last_rendered(), ["Address"]);
+    assert!(std::fs::read_to_string(dir.join("Types.fs"))
+        .unwrap()
+        .contains("street : int"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
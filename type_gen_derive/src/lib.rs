@@ -0,0 +1,33 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, DeriveInput};
+
+///Registers the type, so `type_gen::gen_exported` and `type_gen::Generator::add_exported` generate
+///it. The type needs to implement `schemars::JsonSchema`.
+///
+///Generic types can't be registered this way, as it isn't known which arguments to use.
+///Use `type_gen::export_type!` for the instances that should be generated instead.
+#[proc_macro_attribute]
+pub fn export(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = proc_macro2::TokenStream::from(args);
+    let input = parse_macro_input!(item as DeriveInput);
+    if !args.is_empty() {
+        return syn::Error::new(args.span(), "`export` doesn't take any arguments")
+            .to_compile_error()
+            .into();
+    }
+    if !input.generics.params.is_empty() {
+        return syn::Error::new(
+            input.generics.span(),
+            "generic types can't be exported, use `type_gen::export_type!` with the arguments to use instead",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let name = &input.ident;
+    quote! {
+        #input
+        ::type_gen::export_type!(#name);
+    }
+    .into()
+}